@@ -0,0 +1,131 @@
+use std::collections::{HashMap, HashSet};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::stream::StreamBundle;
+use crate::threshold::{Level, Threshold};
+
+/// Shell command run when a series goes past a threshold. It fires when the last `persist`
+/// samples are all beyond the limit and the `every` interval has passed since the previous run
+/// for that threshold and series, then not again until the series comes back.
+///
+/// The command gets the details in its environment: `GRUST_SERIES`, `GRUST_VALUE`,
+/// `GRUST_THRESHOLD` (as in `latency>200`), `GRUST_LEVEL` (`warning` or `critical`) and, for
+/// timed series, `GRUST_TIME` in epoch seconds.
+pub struct Trigger {
+    pub command: String,
+    pub persist: usize,
+    pub every: Duration,
+    /// (threshold, series) indices the command ran for, still beyond their limit at the last check
+    active: HashSet<(usize, usize)>,
+    last_run: HashMap<(usize, usize), Instant>,
+    /// Commands still running, waited for so they don't linger as zombies
+    children: Vec<Child>,
+}
+
+impl Trigger {
+
+    pub fn new(command: &str, persist: usize, every: Duration) -> Trigger {
+        Trigger {
+            command: command.to_string(),
+            persist: persist.max(1),
+            every,
+            active: HashSet::new(),
+            last_run: HashMap::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Runs the command for every threshold newly held past its limit. Returns what was run,
+    /// or why it couldn't be.
+    pub fn check(&mut self, bundle: &StreamBundle<f32>, thresholds: &[Threshold]) -> Vec<Result<String, String>> {
+        self.check_at(bundle, thresholds, Instant::now())
+    }
+
+    /// `check` as of `now`
+    fn check_at(&mut self, bundle: &StreamBundle<f32>, thresholds: &[Threshold], now: Instant) -> Vec<Result<String, String>> {
+        self.children.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+
+        let mut active = HashSet::new();
+        let mut outcome = Vec::new();
+        for (s, (name, stream)) in bundle.iter().enumerate() {
+            let data = stream.data();
+            if data.len() < self.persist { continue }
+            let recent = &data[data.len() - self.persist..];
+            for (t, threshold) in thresholds.iter().enumerate() {
+                if !threshold.applies_to(name) || !recent.iter().all(|v| threshold.crossed(*v as f64)) {
+                    continue
+                }
+                if self.active.contains(&(t, s)) {
+                    active.insert((t, s));
+                    continue
+                }
+                // Held back by `every`, it stays pending and fires once the interval is over
+                if let Some(last) = self.last_run.get(&(t, s)) {
+                    if now.duration_since(*last) < self.every { continue }
+                }
+                active.insert((t, s));
+                self.last_run.insert((t, s), now);
+                let time = stream.time().and_then(|time| time.last().copied());
+                outcome.push(self.run(name, recent[recent.len() - 1], threshold, time));
+            }
+        }
+        self.active = active;
+        outcome
+    }
+
+    fn run(&mut self, series: &str, value: f32, threshold: &Threshold, time: Option<f64>) -> Result<String, String> {
+        let level = match threshold.level {
+            Level::Warning => "warning",
+            Level::Critical => "critical",
+        };
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(&self.command)
+            .env("GRUST_SERIES", series)
+            .env("GRUST_VALUE", value.to_string())
+            .env("GRUST_THRESHOLD", threshold.label())
+            .env("GRUST_LEVEL", level)
+            // Its output would land on top of the plot
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(time) = time { command.env("GRUST_TIME", time.to_string()); }
+        match command.spawn() {
+            Ok(child) => {
+                self.children.push(child);
+                Ok(format!("ran alert command for {} {}", series, threshold.label()))
+            },
+            Err(e) => Err(format!("alert command failed: {}", e)),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::Stream;
+
+    #[test]
+    fn held_back_crossings_fire_once_the_interval_is_over() {
+        let every = Duration::from_secs(10);
+        let mut trigger = Trigger::new("true", 1, every);
+        let thresholds = [Threshold::parse("x>1").unwrap()];
+        let mut bundle = StreamBundle::new();
+        bundle.push("x", Stream::new(vec![2f32].into_iter()));
+        let start = Instant::now();
+        let mut check = |value: f32, secs: u64| {
+            bundle.streams_mut()[0].push(value, None);
+            trigger.check_at(&bundle, &thresholds, start + Duration::from_secs(secs)).len()
+        };
+        assert_eq!(check(2., 0), 1);
+        // Held past the limit, it doesn't fire again
+        assert_eq!(check(3., 3), 0);
+        // Back and past again too soon after the first run
+        assert_eq!(check(0., 4), 0);
+        assert_eq!(check(2., 5), 0);
+        assert_eq!(check(2., 8), 0);
+        assert_eq!(check(2., 12), 1);
+        assert_eq!(check(2., 30), 0);
+    }
+}
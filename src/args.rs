@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+use crate::alert::Trigger;
+use crate::axis::Axis;
+use crate::derive::Derived;
+use crate::draw::heatmap::Palette;
+use crate::expr::Computed;
+use crate::fft::WindowFunction;
+use crate::interact::View;
+use crate::source::{Source, TimeSource};
+use crate::stream::Bins;
+use crate::threshold::Threshold;
+use crate::time::{self, TimeFormat, Timestamp};
+use crate::yrange::RangePolicy;
+
+pub const USAGE: &str = "\
+usage: grust [options] FILE
+
+  --time-col N        take timestamps from column N (1-based)
+  --time-format FMT   s, ms, rfc3339 or auto (default)
+  --arrival           timestamp samples with the time they were read
+  --from TIME         first instant to show (epoch seconds or rfc3339)
+  --to TIME           last instant to show
+  -f, --follow        keep reading lines appended to FILE
+  --scatter X:Y       plot column Y against column X (repeatable), 'x' toggles the view
+  --density           shade scatter cells by how many points fall in them
+  --palette NAME      heatmap colors: viridis (default), magma or gray
+  --theme NAME        screen colors: dark (default), light or one defined in
+                      ~/.config/grust/themes, 't' cycles them while running
+  --sparkline N       print an N wide sparkline of each column and exit
+  --bins N            histogram bins: a count, fd (Freedman-Diaconis, default) or sturges
+  --derive COL:OP     overlay a series derived from column COL (repeatable), OP is one of
+                      sma:N, ema:N, diff, deriv, cumsum, integral or rate (counters)
+  --expr NAME=EXPR    add a series computed from a formula over the columns (repeatable),
+                      e.g. 'util=busy/total*100', also ':let NAME = EXPR' while running
+  --yscale NAME       value axis of the series view: linear (default), log or symlog
+  --yrange [VIEW=]P   value range policy, for one of the series, bars, heatmap or spectrum
+                      views or all of them: auto[:PADDING] (default), sticky[:SECONDS]
+                      or fixed:MIN:MAX (repeatable), 'y' cycles them while running
+  --threshold SPEC    reference line (repeatable): [SERIES]>VALUE or [SERIES]<VALUE colors
+                      samples beyond it and alerts when the last one crosses, @TIME
+                      marks an instant. Add ',warn' for yellow instead of red
+  --on-alert CMD      shell command run when a series goes past a threshold, with the
+                      details in GRUST_SERIES, GRUST_VALUE, GRUST_THRESHOLD, GRUST_LEVEL
+                      and GRUST_TIME
+  --alert-after N     only when the last N samples are all past it (default 1)
+  --alert-every SECS  run at most once every SECS per threshold and series (default 60)
+  --rate HZ           sample rate for the spectrum (default: from timestamps, else 1)
+  --window NAME       spectrum window: hann (default), hamming or none
+  --log-freq          logarithmic frequency axis on the spectrum
+  --fft-size N        samples per spectrogram window, a power of two (default 128)
+";
+
+pub struct Args {
+    pub source: Source,
+    pub follow: bool,
+    pub from: Option<Timestamp>,
+    pub to: Option<Timestamp>,
+    pub scatter: Vec<(String, String)>,
+    pub density: bool,
+    pub derived: Vec<Derived>,
+    pub computed: Vec<Computed>,
+    pub bins: Bins,
+    pub yscale: Axis,
+    /// Range policies by view, None standing for every view
+    pub yranges: Vec<(Option<View>, RangePolicy)>,
+    pub thresholds: Vec<Threshold>,
+    pub trigger: Option<Trigger>,
+    pub sparkline: Option<usize>,
+    pub palette: Palette,
+    /// Name of the theme to start with, the first one when missing
+    pub theme: Option<String>,
+    pub rate: Option<f64>,
+    pub window: WindowFunction,
+    pub log_freq: bool,
+    pub fft_size: usize,
+}
+
+fn value<I: Iterator<Item=String>>(args: &mut I, flag: &str) -> Result<String, String> {
+    args.next().ok_or(format!("{} needs a value", flag))
+}
+
+fn pair(s: &str) -> Result<(String, String), String> {
+    match s.find(':') {
+        Some(i) if i > 0 && i + 1 < s.len() => Ok((s[..i].to_string(), s[i + 1..].to_string())),
+        _ => Err(format!("expected X:Y columns, got '{}'", s)),
+    }
+}
+
+fn instant(s: &str) -> Result<Timestamp, String> {
+    time::parse(s, TimeFormat::Auto).ok_or(format!("can't read time '{}'", s))
+}
+
+/// `VIEW=POLICY` or just `POLICY`
+fn yrange(s: &str) -> Result<(Option<View>, RangePolicy), String> {
+    let (view, policy) = match s.find('=') {
+        Some(i) => (Some(View::from(&s[..i]).ok_or(format!("no value range in view '{}'", &s[..i]))?), &s[i + 1..]),
+        None => (None, s),
+    };
+    Ok((view, RangePolicy::from(policy).ok_or(format!("bad range policy '{}'", policy))?))
+}
+
+impl Args {
+
+    pub fn parse<I: Iterator<Item=String>>(mut args: I) -> Result<Args, String> {
+        let mut path = None;
+        let mut time_source = TimeSource::Index;
+        let mut format = TimeFormat::Auto;
+        let mut follow = false;
+        let mut from = None;
+        let mut to = None;
+        let mut scatter = Vec::new();
+        let mut density = false;
+        let mut derived = Vec::new();
+        let mut computed = Vec::new();
+        let mut bins = Bins::FreedmanDiaconis;
+        let mut yscale = Axis::Linear;
+        let mut yranges = Vec::new();
+        let mut thresholds = Vec::new();
+        let mut on_alert = None;
+        let mut alert_after = 1;
+        let mut alert_every = 60.;
+        let mut sparkline = None;
+        let mut palette = Palette::Viridis;
+        let mut theme = None;
+        let mut rate = None;
+        let mut window = WindowFunction::Hann;
+        let mut log_freq = false;
+        let mut fft_size = 128;
+
+        while let Some(arg) = args.next() {
+            match &arg[..] {
+                "--time-col" => {
+                    let col = value(&mut args, &arg)?;
+                    match col.parse::<usize>() {
+                        Ok(c) if c > 0 => time_source = TimeSource::Column(c),
+                        _ => return Err(format!("bad column '{}'", col)),
+                    }
+                },
+                "--time-format" => {
+                    let fmt = value(&mut args, &arg)?;
+                    format = TimeFormat::from(&fmt).ok_or(format!("unknown time format '{}'", fmt))?;
+                },
+                "--arrival" => time_source = TimeSource::Arrival,
+                "--from" => from = Some(instant(&value(&mut args, &arg)?)?),
+                "--to" => to = Some(instant(&value(&mut args, &arg)?)?),
+                "-f" | "--follow" => follow = true,
+                "--scatter" => scatter.push(pair(&value(&mut args, &arg)?)?),
+                "--density" => density = true,
+                "--derive" => derived.push(Derived::parse(&value(&mut args, &arg)?)?),
+                "--expr" => computed.push(Computed::parse(&value(&mut args, &arg)?)?),
+                "--sparkline" => {
+                    let n = value(&mut args, &arg)?;
+                    sparkline = Some(n.parse::<usize>().map_err(|_| format!("bad width '{}'", n))?);
+                },
+                "--palette" => {
+                    let p = value(&mut args, &arg)?;
+                    palette = Palette::from(&p).ok_or(format!("unknown palette '{}'", p))?;
+                },
+                "--theme" => theme = Some(value(&mut args, &arg)?),
+                "--bins" => {
+                    let b = value(&mut args, &arg)?;
+                    bins = Bins::from(&b).ok_or(format!("bad bin count '{}'", b))?;
+                },
+                "--yscale" => {
+                    let y = value(&mut args, &arg)?;
+                    yscale = Axis::from(&y).ok_or(format!("unknown scale '{}'", y))?;
+                },
+                "--yrange" => yranges.push(yrange(&value(&mut args, &arg)?)?),
+                "--threshold" => thresholds.push(Threshold::parse(&value(&mut args, &arg)?)?),
+                "--on-alert" => on_alert = Some(value(&mut args, &arg)?),
+                "--alert-after" => {
+                    let n = value(&mut args, &arg)?;
+                    match n.parse::<usize>() {
+                        Ok(n) if n > 0 => alert_after = n,
+                        _ => return Err(format!("bad sample count '{}'", n)),
+                    }
+                },
+                "--alert-every" => {
+                    let secs = value(&mut args, &arg)?;
+                    match secs.parse::<f64>() {
+                        Ok(secs) if secs >= 0. && secs.is_finite() => alert_every = secs,
+                        _ => return Err(format!("bad interval '{}'", secs)),
+                    }
+                },
+                "--rate" => {
+                    let r = value(&mut args, &arg)?;
+                    match r.parse::<f64>() {
+                        Ok(hz) if hz > 0. => rate = Some(hz),
+                        _ => return Err(format!("bad sample rate '{}'", r)),
+                    }
+                },
+                "--window" => {
+                    let w = value(&mut args, &arg)?;
+                    window = WindowFunction::from(&w).ok_or(format!("unknown window '{}'", w))?;
+                },
+                "--log-freq" => log_freq = true,
+                "--fft-size" => {
+                    let n = value(&mut args, &arg)?;
+                    match n.parse::<usize>() {
+                        Ok(size) if size >= 4 && size.is_power_of_two() => fft_size = size,
+                        _ => return Err(format!("bad FFT size '{}', expected a power of two", n)),
+                    }
+                },
+                "-h" | "--help" => return Err(String::new()),
+                _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+                _ => path = Some(arg),
+            }
+        }
+
+        let path = path.ok_or_else(|| "missing FILE".to_string())?;
+        Ok(Args {
+            source: Source::new(&path, time_source, format),
+            follow,
+            from,
+            to,
+            scatter,
+            density,
+            derived,
+            computed,
+            bins,
+            yscale,
+            yranges,
+            thresholds,
+            trigger: on_alert.map(|cmd| Trigger::new(&cmd, alert_after, Duration::from_secs_f64(alert_every))),
+            sparkline,
+            palette,
+            theme,
+            rate,
+            window,
+            log_freq,
+            fft_size,
+        })
+    }
+
+}
@@ -0,0 +1,153 @@
+use crate::time;
+
+/// How an axis maps values to cells and where it puts its ticks
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Axis {
+    Linear,
+    /// Base 10 logarithmic, for positive values spanning orders of magnitude
+    Log,
+    /// Logarithmic on both sides of a linear stretch around zero, for values of either sign
+    /// spanning orders of magnitude
+    SymLog,
+    /// Linear in unix timestamps, labeled with wall-clock time
+    Time,
+}
+
+impl Axis {
+
+    /// Value axis by name: linear, log or symlog
+    pub fn from(s: &str) -> Option<Axis> {
+        match s {
+            "linear" | "lin" => Some(Axis::Linear),
+            "log" | "log10" => Some(Axis::Log),
+            "symlog" => Some(Axis::SymLog),
+            _ => None
+        }
+    }
+
+    /// Value axis after this one when cycling with a key: linear, log, symlog
+    pub fn next_scale(self) -> Axis {
+        match self {
+            Axis::Linear => Axis::Log,
+            Axis::Log => Axis::SymLog,
+            _ => Axis::Linear,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Axis::Linear => "linear",
+            Axis::Log => "log",
+            Axis::SymLog => "symlog",
+            Axis::Time => "time",
+        }
+    }
+
+    /// Coordinate in which the axis is linear. Scales and offsets of a window are kept in it.
+    pub fn forward(self, v: f64) -> f64 {
+        match self {
+            Axis::Log => v.log10(),
+            Axis::SymLog => v.signum() * (1. + v.abs()).log10(),
+            _ => v,
+        }
+    }
+
+    pub fn inverse(self, u: f64) -> f64 {
+        match self {
+            Axis::Log => 10f64.powf(u),
+            Axis::SymLog => u.signum() * (10f64.powf(u.abs()) - 1.),
+            _ => u,
+        }
+    }
+
+    /// Tick values in [min, max], about `n` of them, and the step between them
+    pub fn ticks(self, min: f64, max: f64, n: usize) -> (f64, Vec<f64>) {
+        match self {
+            Axis::Linear => linear_ticks(min, max, n),
+            Axis::Log => log_ticks(min, max, n),
+            Axis::SymLog => symlog_ticks(min, max, n),
+            Axis::Time => time::ticks(min, max, n),
+        }
+    }
+
+    pub fn label(self, v: f64, step: f64) -> String {
+        match self {
+            Axis::Linear => format_value(v, step),
+            // Ticks are a factor apart, each value is its own step
+            Axis::Log | Axis::SymLog => format_value(v, v.abs()),
+            Axis::Time => time::format_label(v, step),
+        }
+    }
+
+}
+
+/// Step out of the 1-2-5 sequence giving at most `n` intervals over `span`
+pub fn nice_step(span: f64, n: usize) -> f64 {
+    if span <= 0. || !span.is_finite() { return 1. }
+    let raw = span / n.max(1) as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    [1., 2., 5., 10.].iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= raw)
+        .unwrap_or(10. * magnitude)
+}
+
+/// Round-valued ticks inside [min, max], at most `n` + 1 of them
+pub fn linear_ticks(min: f64, max: f64, n: usize) -> (f64, Vec<f64>) {
+    let step = nice_step(max - min, n);
+    let first = (min / step).ceil() * step;
+    let ticks = (0..)
+        .map(|i| first + i as f64 * step)
+        .take_while(|t| *t <= max + step * 1e-9)
+        .collect();
+    (step, ticks)
+}
+
+/// Label for a tick value, with as many decimals as the step needs and no more
+pub fn format_value(v: f64, step: f64) -> String {
+    let v = if v.abs() < step * 1e-9 { 0. } else { v };
+    if v != 0. && (v.abs() >= 1e6 || v.abs() < 1e-3) {
+        return format!("{:.1e}", v)
+    }
+    let decimals = if step >= 1. || step <= 0. { 0 } else { (-step.log10().floor()) as usize };
+    format!("{:.*}", decimals, v)
+}
+
+/// Powers of ten inside [min, max], thinned out to at most `n`. Narrow ranges holding less than
+/// two of them also get the 2 and 5 multiples. Both ends must be positive.
+pub fn log_ticks(min: f64, max: f64, n: usize) -> (f64, Vec<f64>) {
+    if min <= 0. || max <= min { return (1., Vec::new()) }
+    let (lo, hi) = (min.log10().ceil() as i32, max.log10().floor() as i32);
+    let decades = (hi - lo + 1).max(0) as usize;
+
+    let ticks = if decades >= 2 {
+        let every = decades.div_ceil(n.max(1));
+        (lo..=hi).step_by(every).map(|e| 10f64.powi(e)).collect()
+    }
+    else {
+        (lo - 1..=hi)
+            .flat_map(|e| [1., 2., 5.].iter().map(move |m| m * 10f64.powi(e)))
+            .filter(|t| *t >= min && *t <= max)
+            .collect()
+    };
+    (10., ticks)
+}
+
+/// Zero and the powers of ten of either sign inside [min, max], at most `n` of them, keeping
+/// the largest magnitudes when thinning
+pub fn symlog_ticks(min: f64, max: f64, n: usize) -> (f64, Vec<f64>) {
+    if max <= min { return (1., Vec::new()) }
+    let top = min.abs().max(max.abs()).log10().floor().max(0.) as i32;
+    let mut decades: Vec<i32> = (0..=top).collect();
+    let sides = if min < 0. && max > 0. { 2 } else { 1 };
+    let every = (decades.len() * sides).div_ceil(n.max(1)).max(1);
+    decades = decades.into_iter().rev().step_by(every).collect();
+
+    let mut ticks: Vec<f64> = decades.iter()
+        .flat_map(|e| vec![-(10f64.powi(*e)), 10f64.powi(*e)])
+        .chain(std::iter::once(0.))
+        .filter(|t| *t >= min && *t <= max)
+        .collect();
+    ticks.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    (1., ticks)
+}
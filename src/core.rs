@@ -1,19 +1,30 @@
 
 use crate::draw;
+use crate::fft;
+use crate::threshold::Level;
 use crate::interact;
 
-use interact::interact;
+use interact::{interact, AppState, State, View};
 use draw::{DrawResult,DrawError};
+use draw::colors::{pair, series_color};
+use draw::plot::{Orientation, PlotArea};
+use draw::heatmap;
+use draw::sparkline;
+use draw::stats;
+use draw::theme;
+use draw::windows::{NcResult, ScaledWindow, Window, YX};
 
 
-pub fn start_interface() -> DrawResult {
+pub fn start_interface(app: &mut AppState) -> DrawResult {
 
-    while draw_interface().is_ok() { }
+    theme::set(app.themes[app.theme].clone());
+    if app.feed.is_some() { draw::input_timeout(100) }
+    while draw_interface(app).is_ok() { }
     Err(DrawError::CleanExit)
 
 }
 
-pub fn draw_interface() -> DrawResult {
+pub fn draw_interface(app: &mut AppState) -> DrawResult {
 
     // if let Some(cwd) = state.current() {
     //     if let Some(ref mut s) = cwd.to_str() {
@@ -24,9 +35,92 @@ pub fn draw_interface() -> DrawResult {
     //     }
     // }
 
-    let mut main_w = draw::main_window();
-    draw::sub_window(&mut main_w.window);
-    interact()
+    app.poll_feed();
+    if app.check_thresholds() { draw::flash_screen() }
+    let panel = if app.stats { stats::PANEL_WIDTH } else { 0 };
+    let mut main_w = draw::main_window(panel);
+    if draw_view(app, &mut main_w).is_ok() { app.scales = Some(draw::plot::Scales::of(&main_w)) }
+    let _ = main_w.window.wrefresh();
+    if app.stats {
+        let mut panel_w = draw::side_panel(panel);
+        let _ = draw_stats(app, &mut panel_w);
+        let _ = panel_w.wrefresh();
+    }
+    match app.state {
+        State::Command(ref line) => draw::status_line(&format!(":{}", line), true, None),
+        State::Normal => match app.alert {
+            Some((ref text, level)) => draw::status_line(text, false, Some(alert_color(level))),
+            None => draw::status_line(app.message.as_deref().unwrap_or(""), false, None),
+        },
+    }
+    interact(app)
 
 }
 
+fn alert_color(level: Level) -> i16 {
+    pair(theme::with(|t| t.background), draw::plot::level_hue(level))
+}
+
+/// Figures of every series over the part of it on screen
+fn draw_stats(app: &AppState, w: &mut Window) -> NcResult {
+    let entries: Vec<_> = app.named_frames().into_iter()
+        .enumerate()
+        .map(|(i, (name, frame))| (name, frame.stats(), series_color(i)))
+        .collect();
+    stats::draw_stats(w, &entries)
+}
+
+/// Draws the current view of the data into the main window
+fn draw_view(app: &AppState, w: &mut ScaledWindow) -> NcResult {
+    match app.view {
+        View::Series => draw::plot::plot_frames(w, &app.named_frames(), app.yscale, app.yrange(View::Series), &app.thresholds),
+        View::Scatter => {
+            let (names, pairs) = app.scatter_frames();
+            draw::plot::plot_scatter(w, &names, &pairs, app.density)
+        },
+        View::Histogram => match app.selected_frame() {
+            Some((name, frame)) => {
+                let PlotArea { rows, cols, .. } = PlotArea::of(&w.window);
+                let limit = if app.orientation == Orientation::Vertical { cols } else { rows };
+                let hist = frame.histogram(app.bins, limit.max(1) as usize);
+                let cpair = series_color(app.selected);
+                draw::plot::plot_histogram(w, name, &hist, app.orientation, cpair)?;
+                // The series over time, next to its distribution
+                let width = (cols / 3).min(40);
+                let x = w.window.shape().size.1 - width - 1;
+                sparkline::draw_sparkline(&mut w.window, YX(0, x), &frame, width as usize, cpair)
+            },
+            None => Ok(0),
+        },
+        View::Bars => match app.selected_frame() {
+            Some((name, frame)) => draw::plot::plot_bars(w, name, &frame, series_color(app.selected), app.yrange(View::Bars)),
+            None => Ok(0),
+        },
+        View::Heatmap => match app.selected_frame() {
+            Some((name, frame)) => {
+                let colormap = heatmap::Colormap::init(app.palette);
+                heatmap::plot_distribution(w, name, &frame, &colormap, app.yrange(View::Heatmap))
+            },
+            None => Ok(0),
+        },
+        View::Spectrum => match app.selected_frame() {
+            Some((name, frame)) => {
+                let rate = fft::sample_rate(&frame, app.rate);
+                let spectrum = fft::Spectrum::of(frame.data(), rate, app.window);
+                let label = format!("{} ({}, peak {})", name, app.window.name(),
+                    spectrum.peak().map_or(String::from("-"), |f| format!("{:.4} Hz", f)));
+                draw::plot::plot_spectrum(w, &label, &spectrum, app.log_freq, series_color(app.selected), app.yrange(View::Spectrum))
+            },
+            None => Ok(0),
+        },
+        View::Spectrogram => match app.selected_frame() {
+            Some((name, frame)) => {
+                let rate = fft::sample_rate(&frame, app.rate);
+                let colormap = heatmap::Colormap::init(app.palette);
+                let label = format!("{} ({})", name, app.window.name());
+                heatmap::plot_spectrogram(w, &label, &frame, rate, app.window, app.fft_size, &colormap)
+            },
+            None => Ok(0),
+        },
+    }
+}
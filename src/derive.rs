@@ -0,0 +1,196 @@
+use crate::stream::{Stream, StreamBundle};
+use crate::time::Timestamp;
+
+/// Operator making a new stream out of an existing one, sample for sample. Operators on
+/// differences have nothing to say about the first sample and repeat the second one there, or
+/// give 0 while it is the only one.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Transform {
+    /// Simple moving average over the last N samples
+    Sma(usize),
+    /// Exponential moving average with the smoothing of an N sample SMA, alpha = 2 / (N + 1)
+    Ema(usize),
+    /// Difference with the previous sample
+    Diff,
+    /// Change per second, or per sample without timestamps
+    Derivative,
+    CumSum,
+    /// Trapezoidal area under the series, in value seconds (or value samples)
+    Integral,
+    /// Per second increase of a counter. A drop means the counter was reset, and the new value
+    /// is taken as the increase since the reset.
+    Rate,
+}
+
+impl Transform {
+
+    /// `sma:N`, `ema:N`, `diff`, `deriv`, `cumsum`, `integral` or `rate`
+    pub fn from(s: &str) -> Option<Transform> {
+        let (op, n) = match s.find(':') {
+            Some(i) => (&s[..i], Some(s[i + 1..].parse::<usize>().ok().filter(|n| *n > 0)?)),
+            None => (s, None),
+        };
+        match (op, n) {
+            ("sma", Some(n)) => Some(Transform::Sma(n)),
+            ("ema", Some(n)) => Some(Transform::Ema(n)),
+            ("diff", None) => Some(Transform::Diff),
+            ("deriv" | "derivative", None) => Some(Transform::Derivative),
+            ("cumsum", None) => Some(Transform::CumSum),
+            ("integral", None) => Some(Transform::Integral),
+            ("rate", None) => Some(Transform::Rate),
+            _ => None
+        }
+    }
+
+    /// Suffix naming the derived series, as in `latency.sma10`
+    pub fn name(self) -> String {
+        match self {
+            Transform::Sma(n) => format!("sma{}", n),
+            Transform::Ema(n) => format!("ema{}", n),
+            Transform::Diff => String::from("diff"),
+            Transform::Derivative => String::from("deriv"),
+            Transform::CumSum => String::from("cumsum"),
+            Transform::Integral => String::from("integral"),
+            Transform::Rate => String::from("rate"),
+        }
+    }
+
+    /// Values of the transformed series, as many as `data`
+    pub fn apply(self, data: &[f32], time: Option<&[Timestamp]>) -> Vec<f32> {
+        let dt = |i: usize| match time {
+            Some(t) => t[i] - t[i - 1],
+            None => 1.,
+        };
+        let steps = |f: &dyn Fn(usize) -> f32| {
+            let mut out: Vec<f32> = (1..data.len()).map(f).collect();
+            if !data.is_empty() { out.insert(0, out.first().cloned().unwrap_or(0.)) }
+            out
+        };
+        match self {
+            Transform::Sma(n) => {
+                let mut sum = 0.;
+                data.iter().enumerate().map(|(i, v)| {
+                    sum += *v as f64;
+                    if i >= n { sum -= data[i - n] as f64 }
+                    (sum / (i + 1).min(n) as f64) as f32
+                }).collect()
+            },
+            Transform::Ema(n) => {
+                let alpha = 2. / (n as f32 + 1.);
+                let mut avg = match data.first() { Some(v) => *v, None => return Vec::new() };
+                data.iter().map(|v| { avg += alpha * (v - avg); avg }).collect()
+            },
+            Transform::Diff => steps(&|i| data[i] - data[i - 1]),
+            Transform::Derivative => steps(&|i| {
+                let dt = dt(i);
+                if dt > 0. { ((data[i] - data[i - 1]) as f64 / dt) as f32 } else { 0. }
+            }),
+            Transform::CumSum => {
+                let mut sum = 0.;
+                data.iter().map(|v| { sum += *v as f64; sum as f32 }).collect()
+            },
+            Transform::Integral => {
+                let mut area = 0.;
+                let mut out = vec![0.; data.len().min(1)];
+                for i in 1..data.len() {
+                    area += (data[i] + data[i - 1]) as f64 / 2. * dt(i);
+                    out.push(area as f32);
+                }
+                out
+            },
+            Transform::Rate => steps(&|i| {
+                let increase = if data[i] >= data[i - 1] { data[i] - data[i - 1] } else { data[i] };
+                let dt = dt(i);
+                if dt > 0. { (increase as f64 / dt) as f32 } else { 0. }
+            }),
+        }
+    }
+
+}
+
+/// A transform of one of the input columns
+#[derive(Clone,Debug)]
+pub struct Derived {
+    pub column: String,
+    pub transform: Transform,
+}
+
+impl Derived {
+
+    /// `COLUMN:OP[:N]`, as given to `--derive`
+    pub fn parse(s: &str) -> Result<Derived, String> {
+        let i = s.find(':').filter(|i| *i > 0).ok_or(format!("expected COLUMN:OP, got '{}'", s))?;
+        let transform = Transform::from(&s[i + 1..]).ok_or(format!("unknown transform '{}'", &s[i + 1..]))?;
+        Ok(Derived { column: s[..i].to_string(), transform })
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}.{}", self.column, self.transform.name())
+    }
+
+    pub fn stream(&self, input: &Stream<f32>) -> Option<Stream<f32>> {
+        if input.is_empty() { return None }
+        let values = self.transform.apply(input.data(), input.time());
+        Some(match input.time() {
+            Some(time) => Stream::with_time(values.into_iter(), time.to_vec()),
+            None => Stream::new(values.into_iter()),
+        })
+    }
+
+}
+
+/// Computes every derived series from the current input columns and puts it in the bundle,
+/// replacing what an earlier call left there. Derived series may build on each other when
+/// listed after the one they use.
+pub fn update(bundle: &mut StreamBundle<f32>, derived: &[Derived]) {
+    for d in derived {
+        let stream = match bundle.get(&d.column).and_then(|s| d.stream(s)) {
+            Some(stream) => stream,
+            None => continue,
+        };
+        bundle.set(&d.name(), stream);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transforms_by_hand() {
+        let apply = |t: &str, data: &[f32], time: Option<&[f64]>| Transform::from(t).unwrap().apply(data, time);
+        // Averages over what there is until N samples are in
+        assert_eq!(apply("sma:3", &[1., 2., 3., 4., 5.], None), [1., 1.5, 2., 3., 4.]);
+        assert_eq!(apply("ema:3", &[4., 6., 6.], None), [4., 5., 5.5]);
+        assert_eq!(apply("diff", &[1., 3., 6.], None), [2., 2., 3.]);
+        assert_eq!(apply("cumsum", &[1., 3., 6.], None), [1., 4., 10.]);
+
+        // Per sample without timestamps, per second with them, 0 over no time at all
+        assert_eq!(apply("deriv", &[0., 4., 6.], None), [4., 4., 2.]);
+        assert_eq!(apply("deriv", &[0., 4., 6., 10.], Some(&[0., 2., 2., 6.])), [2., 2., 0., 1.]);
+        assert_eq!(apply("integral", &[0., 2., 4.], None), [0., 1., 4.]);
+        assert_eq!(apply("integral", &[0., 2., 4.], Some(&[0., 2., 3.])), [0., 2., 5.]);
+
+        // The counter went back to 0 between the second and third samples
+        assert_eq!(apply("rate", &[10., 15., 3., 8.], Some(&[0., 1., 2., 4.])), [5., 5., 3., 2.5]);
+        assert_eq!(apply("rate", &[10., 15.], Some(&[7., 7.])), [0., 0.]);
+
+        for t in &["sma:2", "ema:2", "diff", "deriv", "cumsum", "integral", "rate"] {
+            assert_eq!(apply(t, &[], None), [] as [f32; 0], "{}", t);
+            assert_eq!(apply(t, &[3.], None).len(), 1, "{}", t);
+        }
+    }
+
+    #[test]
+    fn transform_specs() {
+        assert_eq!(Transform::from("sma:10"), Some(Transform::Sma(10)));
+        assert_eq!(Transform::from("derivative"), Some(Transform::Derivative));
+        for bad in &["sma", "sma:0", "ema:x", "diff:2", "median:3"] {
+            assert_eq!(Transform::from(bad), None, "{}", bad);
+        }
+        let derived = Derived::parse("latency:sma:10").unwrap();
+        assert_eq!(derived.name(), "latency.sma10");
+        assert_eq!(Derived::parse(":diff").unwrap_err(), "expected COLUMN:OP, got ':diff'");
+        assert_eq!(Derived::parse("x:sma").unwrap_err(), "unknown transform 'sma'");
+    }
+}
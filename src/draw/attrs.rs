@@ -0,0 +1,24 @@
+/// Style of a cell: a color pair and text attributes. Bits are laid out as in ncurses'
+/// `attr_t`, so its backend takes them as they are.
+pub type Attrs = u32;
+
+/// Bits holding the color pair
+pub const A_COLOR: Attrs = 0xff << 8;
+pub const A_UNDERLINE: Attrs = 1 << 17;
+pub const A_REVERSE: Attrs = 1 << 18;
+pub const A_BOLD: Attrs = 1 << 21;
+
+/// Attributes drawing in color pair `cpair`
+pub fn color_pair(cpair: i16) -> Attrs {
+    ((cpair as Attrs) << 8) & A_COLOR
+}
+
+/// Color pair of `attrs`
+pub fn pair_number(attrs: Attrs) -> i16 {
+    ((attrs & A_COLOR) >> 8) as i16
+}
+
+/// `attrs` in the color pair of `background` when they have none of their own
+pub fn with_background(attrs: Attrs, background: Attrs) -> Attrs {
+    if attrs & A_COLOR == 0 { attrs | (background & A_COLOR) } else { attrs }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::io::{self, Stdout, Write};
+use std::thread;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind};
+use crossterm::style::{self, Attribute, Color, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+
+use super::{Backend, Cell, Input};
+use crate::draw::attrs::{pair_number, with_background, Attrs, A_BOLD, A_COLOR, A_REVERSE, A_UNDERLINE};
+use crate::draw::keys::*;
+use crate::draw::windows::YX;
+
+/// The terminal through crossterm, which needs no system library. Drawing goes to a buffer,
+/// and each flush sends the cells that differ from what the terminal shows. Colors given an
+/// rgb value are sent as 24 bit ones, on terminals that take them.
+pub struct Crossterm {
+    out: Stdout,
+    size: YX,
+    attrs: Attrs,
+    /// Attributes of blank cells, see `set_background`
+    background: Attrs,
+    /// What gets drawn, shown on the next flush
+    back: Vec<Vec<Cell>>,
+    /// What the terminal shows
+    front: Vec<Vec<Cell>>,
+    cursor: Option<YX>,
+    /// Milliseconds `poll_input` waits, forever when negative
+    timeout: i32,
+    truecolor: bool,
+    pairs: HashMap<i16, (i16, i16)>,
+    rgb: HashMap<i16, (u8, u8, u8)>,
+}
+
+fn blank_screen(YX(lines, cols): YX, attrs: Attrs) -> Vec<Vec<Cell>> {
+    vec![vec![Cell { ch: ' ', attrs }; cols.max(0) as usize]; lines.max(0) as usize]
+}
+
+/// Code of a key as ncurses gives it
+fn key_code(key: KeyEvent) -> Option<i32> {
+    let code = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => c as i32 & 0x1f,
+        KeyCode::Char(c) => c as i32,
+        KeyCode::Enter => 10,
+        KeyCode::Tab => 9,
+        KeyCode::Esc => 27,
+        KeyCode::Backspace => KEY_BACKSPACE,
+        KeyCode::Up => KEY_UP,
+        KeyCode::Down => KEY_DOWN,
+        KeyCode::Left => KEY_LEFT,
+        KeyCode::Right => KEY_RIGHT,
+        KeyCode::F(n) => KEY_F0 + n as i32,
+        _ => return None,
+    };
+    Some(code)
+}
+
+impl Crossterm {
+
+    pub fn start() -> io::Result<Crossterm> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, event::EnableMouseCapture, cursor::Hide, terminal::Clear(ClearType::All))?;
+        let (cols, rows) = terminal::size()?;
+        let size = YX(rows as i32, cols as i32);
+        Ok(Crossterm {
+            out,
+            size,
+            attrs: 0,
+            background: 0,
+            back: blank_screen(size, 0),
+            front: blank_screen(size, 0),
+            cursor: None,
+            timeout: -1,
+            truecolor: style::available_color_count() == u16::MAX,
+            pairs: HashMap::new(),
+            rgb: HashMap::new(),
+        })
+    }
+
+    /// Starts over on a blank screen of the new size
+    fn resize(&mut self, size: YX) {
+        self.size = size;
+        self.back = blank_screen(size, self.background);
+        // What the cleared terminal shows
+        self.front = blank_screen(size, 0);
+        let _ = queue!(self.out, terminal::Clear(ClearType::All));
+    }
+
+    /// Has the cells in the pairs `changed` sent again on the next flush, as ncurses does when a
+    /// pair or color gets redefined
+    fn repaint(&mut self, changed: impl Fn(i16) -> bool) {
+        for cell in self.front.iter_mut().flatten() {
+            if changed(pair_number(cell.attrs)) { cell.ch = '\0' }
+        }
+    }
+
+    fn color(&self, color: i16) -> Color {
+        match self.rgb.get(&color) {
+            Some(&(r, g, b)) => Color::Rgb { r, g, b },
+            None => Color::AnsiValue(color as u8),
+        }
+    }
+
+    fn set_style(&mut self, attrs: Attrs) -> io::Result<()> {
+        queue!(self.out, SetAttribute(Attribute::Reset))?;
+        if attrs & A_BOLD != 0 { queue!(self.out, SetAttribute(Attribute::Bold))? }
+        if attrs & A_UNDERLINE != 0 { queue!(self.out, SetAttribute(Attribute::Underlined))? }
+        if attrs & A_REVERSE != 0 { queue!(self.out, SetAttribute(Attribute::Reverse))? }
+        let (fg, bg) = self.pair_colors(pair_number(attrs));
+        if fg >= 0 {
+            let color = self.color(fg);
+            queue!(self.out, SetForegroundColor(color))?
+        }
+        if bg >= 0 {
+            let color = self.color(bg);
+            queue!(self.out, SetBackgroundColor(color))?
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        queue!(self.out, cursor::Hide)?;
+        // Cell the terminal cursor is on, and attributes it draws in
+        let mut at = None;
+        let mut current = None;
+        for y in 0..self.back.len() {
+            for x in 0..self.back[y].len() {
+                let cell = self.back[y][x];
+                if cell == self.front[y][x] { continue }
+                if at != Some(YX(y as i32, x as i32)) { queue!(self.out, cursor::MoveTo(x as u16, y as u16))? }
+                if current != Some(cell.attrs) {
+                    self.set_style(cell.attrs)?;
+                    current = Some(cell.attrs);
+                }
+                queue!(self.out, style::Print(cell.ch))?;
+                self.front[y][x] = cell;
+                at = Some(YX(y as i32, x as i32 + 1));
+            }
+        }
+        if current.is_some() { queue!(self.out, SetAttribute(Attribute::Reset))? }
+        if let Some(YX(y, x)) = self.cursor {
+            queue!(self.out, cursor::MoveTo(x as u16, y as u16), cursor::Show)?;
+        }
+        self.out.flush()
+    }
+
+}
+
+impl Backend for Crossterm {
+
+    fn size(&self) -> YX {
+        self.size
+    }
+
+    fn set_attrs(&mut self, attrs: Attrs) {
+        self.attrs = attrs;
+    }
+
+    fn draw_cell(&mut self, YX(y, x): YX, ch: char) {
+        if y < 0 || x < 0 { return }
+        let attrs = with_background(self.attrs, self.background);
+        if let Some(cell) = self.back.get_mut(y as usize).and_then(|row| row.get_mut(x as usize)) {
+            *cell = Cell { ch, attrs };
+        }
+    }
+
+    fn set_background(&mut self, attrs: Attrs) {
+        let old = pair_number(self.background);
+        for cell in self.back.iter_mut().flatten() {
+            if pair_number(cell.attrs) == old { cell.attrs = (cell.attrs & !A_COLOR) | (attrs & A_COLOR) }
+        }
+        self.background = attrs;
+    }
+
+    fn flush(&mut self) {
+        let _ = self.draw();
+    }
+
+    fn poll_input(&mut self) -> Option<Input> {
+        if self.timeout >= 0 && !event::poll(Duration::from_millis(self.timeout as u64)).unwrap_or(false) {
+            return None
+        }
+        match event::read().ok()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key_code(key).map(Input::Key),
+            Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                Some(Input::Click(YX(mouse.row as i32, mouse.column as i32)))
+            },
+            Event::Resize(cols, rows) => {
+                self.resize(YX(rows as i32, cols as i32));
+                Some(Input::Resize(self.size))
+            },
+            _ => None,
+        }
+    }
+
+    fn set_input_timeout(&mut self, ms: i32) {
+        self.timeout = ms;
+    }
+
+    fn set_cursor(&mut self, yx: Option<YX>) {
+        self.cursor = yx;
+    }
+
+    /// Reverse video for a tenth of a second, as ncurses does on xterm
+    fn flash(&mut self) {
+        let _ = write!(self.out, "\x1b[?5h");
+        let _ = self.out.flush();
+        thread::sleep(Duration::from_millis(100));
+        let _ = write!(self.out, "\x1b[?5l");
+        let _ = self.out.flush();
+    }
+
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        self.front.clone()
+    }
+
+    fn colors(&self) -> i32 {
+        match style::available_color_count() {
+            n if n >= 256 => 256,
+            n => n as i32,
+        }
+    }
+
+    fn can_change_color(&self) -> bool {
+        self.truecolor
+    }
+
+    /// As many as the attributes have room for
+    fn color_pairs(&self) -> i32 {
+        256
+    }
+
+    fn init_pair(&mut self, cpair: i16, fg: i16, bg: i16) {
+        if self.pairs.insert(cpair, (fg, bg)).is_some_and(|old| old != (fg, bg)) {
+            self.repaint(|p| p == cpair);
+        }
+    }
+
+    fn init_color(&mut self, color: i16, rgb: (u8, u8, u8)) {
+        if !self.truecolor { return }
+        if self.rgb.insert(color, rgb).is_some_and(|old| old != rgb) {
+            let pairs: Vec<i16> = self.pairs.iter().filter(|(_, &(fg, bg))| fg == color || bg == color).map(|(p, _)| *p).collect();
+            self.repaint(|p| pairs.contains(&p));
+        }
+    }
+
+    fn pair_colors(&self, cpair: i16) -> (i16, i16) {
+        self.pairs.get(&cpair).copied().unwrap_or((-1, -1))
+    }
+
+    fn color_rgb(&self, color: i16) -> Option<(u8, u8, u8)> {
+        if color < 16 { return None }
+        self.rgb.get(&color).copied()
+    }
+
+    fn end(&mut self) {
+        let _ = execute!(self.out, SetAttribute(Attribute::Reset), event::DisableMouseCapture, terminal::LeaveAlternateScreen, cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+
+}
@@ -0,0 +1,181 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use super::{Backend, Cell, Input};
+use crate::draw::attrs::{pair_number, with_background, Attrs, A_COLOR};
+use crate::draw::windows::YX;
+
+/// Screen kept in memory, for drawing without a terminal. Clones share the screen, so one
+/// can be installed and another used to look at what was drawn or to queue keys.
+#[derive(Clone)]
+#[cfg_attr(not(test), allow(dead_code))]
+pub struct Memory(Rc<RefCell<Screen>>);
+
+#[cfg_attr(not(test), allow(dead_code))]
+struct Screen {
+    size: YX,
+    attrs: Attrs,
+    background: Attrs,
+    cells: Vec<Vec<Cell>>,
+    input: VecDeque<Input>,
+    cursor: Option<YX>,
+    colors: i32,
+    can_change: bool,
+    pairs: HashMap<i16, (i16, i16)>,
+    rgb: HashMap<i16, (u8, u8, u8)>,
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+impl Memory {
+
+    /// A screen with the fixed xterm 256 color palette
+    pub fn new(size: YX) -> Memory {
+        Memory::with_colors(size, 256, false)
+    }
+
+    /// A screen with `colors` colors, which can be given any rgb value when `can_change` is set
+    pub fn with_colors(YX(lines, cols): YX, colors: i32, can_change: bool) -> Memory {
+        let cells = vec![vec![Cell::blank(); cols.max(0) as usize]; lines.max(0) as usize];
+        Memory(Rc::new(RefCell::new(Screen {
+            size: YX(lines, cols),
+            attrs: 0,
+            background: 0,
+            cells,
+            input: VecDeque::new(),
+            cursor: None,
+            colors,
+            can_change,
+            pairs: HashMap::new(),
+            rgb: HashMap::new(),
+        })))
+    }
+
+    /// Queues a key code for `poll_input`
+    pub fn push_key(&self, code: i32) {
+        self.0.borrow_mut().input.push_back(Input::Key(code));
+    }
+
+    pub fn cell(&self, YX(y, x): YX) -> Option<Cell> {
+        self.0.borrow().cells.get(y as usize)?.get(x as usize).copied()
+    }
+
+    pub fn cursor(&self) -> Option<YX> {
+        self.0.borrow().cursor
+    }
+
+    /// The characters on screen, a line per row without trailing blanks
+    pub fn text(&self) -> String {
+        self.0.borrow().cells.iter()
+            .map(|row| row.iter().map(|c| c.ch).collect::<String>().trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+}
+
+impl Backend for Memory {
+
+    fn size(&self) -> YX {
+        self.0.borrow().size
+    }
+
+    fn set_attrs(&mut self, attrs: Attrs) {
+        self.0.borrow_mut().attrs = attrs;
+    }
+
+    fn draw_cell(&mut self, YX(y, x): YX, ch: char) {
+        let mut screen = self.0.borrow_mut();
+        let attrs = with_background(screen.attrs, screen.background);
+        if y < 0 || x < 0 { return }
+        if let Some(cell) = screen.cells.get_mut(y as usize).and_then(|row| row.get_mut(x as usize)) {
+            *cell = Cell { ch, attrs };
+        }
+    }
+
+    fn set_background(&mut self, attrs: Attrs) {
+        let mut screen = self.0.borrow_mut();
+        let old = pair_number(screen.background);
+        for cell in screen.cells.iter_mut().flatten() {
+            if pair_number(cell.attrs) == old { cell.attrs = (cell.attrs & !A_COLOR) | (attrs & A_COLOR) }
+        }
+        screen.background = attrs;
+    }
+
+    fn flush(&mut self) {}
+
+    fn poll_input(&mut self) -> Option<Input> {
+        self.0.borrow_mut().input.pop_front()
+    }
+
+    fn set_cursor(&mut self, yx: Option<YX>) {
+        self.0.borrow_mut().cursor = yx;
+    }
+
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        self.0.borrow().cells.clone()
+    }
+
+    fn colors(&self) -> i32 {
+        self.0.borrow().colors
+    }
+
+    fn can_change_color(&self) -> bool {
+        self.0.borrow().can_change
+    }
+
+    fn color_pairs(&self) -> i32 {
+        if self.colors() > 0 { 256 } else { 0 }
+    }
+
+    fn init_pair(&mut self, cpair: i16, fg: i16, bg: i16) {
+        self.0.borrow_mut().pairs.insert(cpair, (fg, bg));
+    }
+
+    fn init_color(&mut self, color: i16, rgb: (u8, u8, u8)) {
+        let mut screen = self.0.borrow_mut();
+        if screen.can_change { screen.rgb.insert(color, rgb); }
+    }
+
+    fn pair_colors(&self, cpair: i16) -> (i16, i16) {
+        self.0.borrow().pairs.get(&cpair).copied().unwrap_or((-1, -1))
+    }
+
+    fn color_rgb(&self, color: i16) -> Option<(u8, u8, u8)> {
+        self.0.borrow().rgb.get(&color).copied()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::draw::attrs::color_pair;
+    use crate::draw::backend::{install, with};
+    use crate::draw::windows::{Shape, Window};
+
+    #[test]
+    fn windows_draw_into_memory() {
+        let screen = Memory::new(YX(4, 10));
+        install(Box::new(screen.clone()));
+
+        let mut w = Window::new(Shape { pos: YX(0, 0), size: YX(4, 10) }, None);
+        w.wattron(color_pair(3)).ok();
+        w.mvwprintw(YX(1, 1), "hello world").ok();
+        w.wattroff(color_pair(3)).ok();
+
+        // Cut off at the edge of the window
+        assert_eq!(screen.text(), "┌────────┐\n│hello wor\n│        │\n└────────┘");
+        assert_eq!(screen.cell(YX(1, 1)), Some(Cell { ch: 'h', attrs: color_pair(3) }));
+        assert_eq!(screen.cell(YX(2, 1)), Some(Cell::blank()));
+
+        crate::draw::status_line(":let", true, None);
+        // The status line keeps off the last column
+        assert_eq!(screen.text().lines().last(), Some(":let     ┘"));
+        assert_eq!(screen.cursor(), Some(YX(3, 4)));
+
+        screen.push_key('q' as i32);
+        assert_eq!(with(|b| b.poll_input()), Some(Input::Key('q' as i32)));
+        assert_eq!(with(|b| b.poll_input()), None);
+    }
+}
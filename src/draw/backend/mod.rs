@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+use std::io;
+
+use super::attrs::Attrs;
+use super::windows::YX;
+
+#[cfg(feature = "crossterm")]
+mod crossterm;
+pub mod memory;
+#[cfg(feature = "ncurses")]
+mod ncurses;
+
+#[cfg(feature = "crossterm")]
+pub use self::crossterm::Crossterm;
+#[cfg(feature = "ncurses")]
+pub use self::ncurses::Ncurses;
+
+#[cfg(not(any(feature = "ncurses", feature = "crossterm")))]
+compile_error!("grust draws through ncurses or crossterm, enable one of those features");
+
+/// A character cell, with the attributes it was drawn in
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    /// Color pair and style, as in `color_pair(n) | A_BOLD`
+    pub attrs: Attrs,
+}
+
+impl Cell {
+    pub fn blank() -> Cell {
+        Cell { ch: ' ', attrs: 0 }
+    }
+}
+
+/// What the user did, as told by `poll_input`
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Input {
+    /// Character code of a key, or one of `keys`
+    Key(i32),
+    /// Left mouse button pressed over a cell
+    Click(YX),
+    /// The terminal got a new size, everything has to be drawn again
+    Resize(YX),
+}
+
+/// What drawing needs from a terminal. Cells are addressed from the top left of the screen,
+/// and drawing past its edges is dropped. Colors are numbered as in ncurses, 0 to 7 are the
+/// standard ones and -1 the default of the terminal, and drawn through numbered pairs.
+pub trait Backend {
+    /// Rows and columns of the screen
+    fn size(&self) -> YX;
+    /// Attributes of what gets drawn from now on
+    fn set_attrs(&mut self, attrs: Attrs);
+    fn draw_cell(&mut self, yx: YX, ch: char);
+    /// Attributes blank cells get, and the color of cells drawn without one, as ncurses' `bkgd`
+    fn set_background(&mut self, _attrs: Attrs) {}
+    /// Draws `s` from `yx` to the right, a cell per character
+    fn print(&mut self, yx: YX, s: &str) {
+        for (i, ch) in s.chars().enumerate() {
+            self.draw_cell(yx + YX(0, i as i32), ch);
+        }
+    }
+    /// Makes what was drawn since the last flush visible
+    fn flush(&mut self);
+    /// Next input, or None if none came within the input timeout
+    fn poll_input(&mut self) -> Option<Input>;
+    /// Milliseconds `poll_input` waits for a key, forever when negative
+    fn set_input_timeout(&mut self, _ms: i32) {}
+    /// Shows the cursor at `yx`, or hides it
+    fn set_cursor(&mut self, _yx: Option<YX>) {}
+    /// Visual bell
+    fn flash(&mut self) {}
+    /// Contents of the screen as of the last flush, row by row
+    fn cells(&self) -> Vec<Vec<Cell>>;
+    /// Number of colors, 0 without color support
+    fn colors(&self) -> i32 { 0 }
+    /// Whether colors past the first 16 can be given any rgb value
+    fn can_change_color(&self) -> bool { false }
+    /// Number of color pairs, pair 0 being the terminal's default colors
+    fn color_pairs(&self) -> i32 { 0 }
+    fn init_pair(&mut self, _cpair: i16, _fg: i16, _bg: i16) {}
+    fn init_color(&mut self, _color: i16, _rgb: (u8, u8, u8)) {}
+    /// Text and background colors of a pair, -1 for the terminal defaults
+    fn pair_colors(&self, _cpair: i16) -> (i16, i16) { (-1, -1) }
+    /// Rgb value of a color past the standard 16, when it was given one with `init_color`
+    fn color_rgb(&self, _color: i16) -> Option<(u8, u8, u8)> { None }
+    /// Leaves the terminal as it was found
+    fn end(&mut self) {}
+}
+
+thread_local! {
+    static BACKEND: RefCell<Option<Box<dyn Backend>>> = RefCell::new(None);
+}
+
+/// Makes `backend` the one drawing goes to, returning the one it replaces
+pub fn install(backend: Box<dyn Backend>) -> Option<Box<dyn Backend>> {
+    BACKEND.with(|b| b.borrow_mut().replace(backend))
+}
+
+/// Runs `f` on the installed backend. Drawing without one is a bug.
+pub fn with<R>(f: impl FnOnce(&mut dyn Backend) -> R) -> R {
+    BACKEND.with(|b| f(b.borrow_mut().as_deref_mut().expect("no drawing backend installed")))
+}
+
+/// Takes over the terminal, through crossterm when built with it
+#[cfg(feature = "crossterm")]
+pub fn terminal() -> io::Result<Box<dyn Backend>> {
+    Ok(Box::new(Crossterm::start()?))
+}
+
+#[cfg(not(feature = "crossterm"))]
+pub fn terminal() -> io::Result<Box<dyn Backend>> {
+    Ok(Box::new(Ncurses::start()))
+}
@@ -0,0 +1,207 @@
+use std::env;
+
+use ncurses::*;
+
+use super::{Backend, Cell, Input};
+use crate::draw::attrs::Attrs;
+use crate::draw::windows::YX;
+
+/// The terminal through ncurses. Everything is drawn on `stdscr`, which ncurses compares with
+/// the screen on each flush to send only what changed.
+pub struct Ncurses;
+
+impl Ncurses {
+
+    pub fn start() -> Ncurses {
+        // utf-8 support
+        setlocale(LcCategory::all, "");
+
+        initscr();
+        raw();
+        keypad(stdscr(), true);
+        noecho();
+        // Escape leaves command mode, don't wait a second for the rest of a sequence
+        set_escdelay(25);
+        curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+        mousemask(BUTTON1_PRESSED as mmask_t, None);
+        // Presses are reported at once instead of waiting to see whether they make a click
+        mouseinterval(0);
+        // getch refreshes stdscr, which would blank the windows drawn before the first key
+        refresh();
+        if has_colors() {
+            start_color();
+            // Color -1 in pairs stands for the terminal's own
+            use_default_colors();
+        }
+        Ncurses
+    }
+
+}
+
+/// `cchar_t` and the calls reading it back, which the ncurses crate leaves out
+mod wide {
+    use ncurses::{attr_t, WINDOW};
+    use std::os::raw::{c_int, c_short, c_void};
+
+    /// Room for a `cchar_t`, its layout is left to `getcchar`
+    #[repr(C)]
+    pub struct CChar([u32; 8]);
+
+    impl CChar {
+        pub fn new() -> CChar {
+            CChar([0; 8])
+        }
+    }
+
+    extern "C" {
+        pub fn mvwin_wch(win: WINDOW, y: c_int, x: c_int, wcval: *mut CChar) -> c_int;
+        pub fn getcchar(wcval: *const CChar, wch: *mut i32, attrs: *mut attr_t, pair: *mut c_short, opts: *mut c_void) -> c_int;
+    }
+}
+
+/// Line drawing character shown for `c` in the alternate character set, as used by `ACS_*`
+fn acs_char(c: char) -> char {
+    match c {
+        'j' => '┘', 'k' => '┐', 'l' => '┌', 'm' => '└', 'n' => '┼',
+        'q' => '─', 't' => '├', 'u' => '┤', 'v' => '┴', 'w' => '┬', 'x' => '│',
+        'a' => '▒', '`' => '◆', 'f' => '°', 'g' => '±', '~' => '·',
+        'o' => '⎺', 's' => '⎽', 'y' => '≤', 'z' => '≥', '{' => 'π', '|' => '≠', '}' => '£',
+        ',' => '←', '+' => '→', '.' => '↓', '-' => '↑', 'h' => '#', 'i' => '␋', '0' => '█',
+        c => c,
+    }
+}
+
+impl Backend for Ncurses {
+
+    fn size(&self) -> YX {
+        let (mut lines, mut cols) = (0, 0);
+        getmaxyx(stdscr(), &mut lines, &mut cols);
+        YX(lines, cols)
+    }
+
+    fn set_attrs(&mut self, attrs: Attrs) {
+        attrset(attrs as NCURSES_ATTR_T);
+    }
+
+    fn draw_cell(&mut self, YX(y, x): YX, ch: char) {
+        mvaddstr(y, x, ch.encode_utf8(&mut [0; 4]));
+    }
+
+    fn set_background(&mut self, attrs: Attrs) {
+        bkgd(' ' as chtype | attrs as chtype);
+    }
+
+    fn print(&mut self, YX(y, x): YX, s: &str) {
+        // Text running off the right edge would wrap to the next line
+        let YX(_, cols) = self.size();
+        if y < 0 || x >= cols { return }
+        let skip = (-x).max(0) as usize;
+        let s: String = s.chars().skip(skip).take((cols - x.max(0)) as usize).collect();
+        mvaddstr(y, x.max(0), &s);
+    }
+
+    fn flush(&mut self) {
+        refresh();
+    }
+
+    fn poll_input(&mut self) -> Option<Input> {
+        match getch() {
+            ERR => None,
+            KEY_RESIZE => {
+                // Starts over on a blank screen, what was drawn got cut or wrapped
+                erase();
+                Some(Input::Resize(self.size()))
+            },
+            KEY_MOUSE => {
+                let mut event = MEVENT { id: 0, x: 0, y: 0, z: 0, bstate: 0 };
+                let pressed = getmouse(&mut event) == OK && event.bstate & BUTTON1_PRESSED as mmask_t != 0;
+                if pressed { Some(Input::Click(YX(event.y, event.x))) } else { None }
+            },
+            code => Some(Input::Key(code)),
+        }
+    }
+
+    fn set_input_timeout(&mut self, ms: i32) {
+        timeout(ms);
+    }
+
+    fn set_cursor(&mut self, yx: Option<YX>) {
+        match yx {
+            Some(YX(y, x)) => {
+                mv(y, x);
+                curs_set(CURSOR_VISIBILITY::CURSOR_VISIBLE);
+            },
+            None => { curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE); },
+        }
+    }
+
+    fn flash(&mut self) {
+        flash();
+    }
+
+    /// Read back from `curscr`, what ncurses believes the terminal shows
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        let YX(lines, cols) = self.size();
+        (0..lines).map(|y| (0..cols).map(|x| {
+            let mut cell = wide::CChar::new();
+            let mut chars = [0i32; 8];
+            let mut attrs: attr_t = 0;
+            let mut pair: i16 = 0;
+            let ok = unsafe {
+                wide::mvwin_wch(curscr(), y, x, &mut cell) == OK &&
+                    wide::getcchar(&cell, chars.as_mut_ptr(), &mut attrs, &mut pair, std::ptr::null_mut()) == OK
+            };
+            if !ok { return Cell::blank() }
+            let ch = char::from_u32(chars[0] as u32).filter(|c| *c != '\0').unwrap_or(' ');
+            let ch = if attrs & A_ALTCHARSET() != 0 { acs_char(ch) } else { ch };
+            let attrs = (attrs & !A_COLOR() & !A_ALTCHARSET()) | COLOR_PAIR(pair);
+            Cell { ch, attrs: attrs as Attrs }
+        }).collect()).collect()
+    }
+
+    fn colors(&self) -> i32 {
+        if has_colors() { COLORS() } else { 0 }
+    }
+
+    /// Only believed on terminals that say they do 24 bit colors, many others claim they take
+    /// new colors and keep showing their own
+    fn can_change_color(&self) -> bool {
+        let truecolor = matches!(env::var("COLORTERM").as_deref(), Ok("truecolor") | Ok("24bit"));
+        truecolor && can_change_color()
+    }
+
+    fn color_pairs(&self) -> i32 {
+        if has_colors() { COLOR_PAIRS() } else { 0 }
+    }
+
+    fn init_pair(&mut self, cpair: i16, fg: i16, bg: i16) {
+        init_pair(cpair, fg, bg);
+    }
+
+    fn init_color(&mut self, color: i16, (r, g, b): (u8, u8, u8)) {
+        // ncurses takes thousandths
+        let scale = |c: u8| ((c as i32 * 1000 + 127) / 255) as i16;
+        init_color(color, scale(r), scale(g), scale(b));
+    }
+
+    fn pair_colors(&self, cpair: i16) -> (i16, i16) {
+        let (mut fg, mut bg) = (-1, -1);
+        if cpair > 0 { pair_content(cpair, &mut fg, &mut bg); }
+        (fg, bg)
+    }
+
+    fn color_rgb(&self, color: i16) -> Option<(u8, u8, u8)> {
+        if color < 16 || !self.can_change_color() { return None }
+        let (mut r, mut g, mut b) = (0, 0, 0);
+        color_content(color, &mut r, &mut g, &mut b);
+        // Colors never defined read as black, the terminal shows its own palette for them
+        if r == 0 && g == 0 && b == 0 { return None }
+        let scale = |c: i16| ((c as i32 * 255 + 500) / 1000).clamp(0, 255) as u8;
+        Some((scale(r), scale(g), scale(b)))
+    }
+
+    fn end(&mut self) {
+        endwin();
+    }
+
+}
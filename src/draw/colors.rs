@@ -1,97 +1,286 @@
-use ncurses::{attr_t};
-use ncurses::{COLOR_PAIR, NCURSES_BITS};
-use ncurses::{
-    init_pair,
-    init_color,
-    COLOR_BLACK,
-    COLOR_RED,
-    COLOR_GREEN,
-    COLOR_YELLOW,
-    COLOR_BLUE,
-    COLOR_MAGENTA,
-    COLOR_CYAN,
-    COLOR_WHITE
-};
-
-// Default colors
-pub static COLOR_BKG: i16 = COLOR_BLACK;
-
-// Default pairs (color over BKG)
-pub static CPAIR_BLA: i16 = 1;
-pub static CPAIR_RED: i16 = 2;
-pub static CPAIR_GRE: i16 = 3;
-pub static CPAIR_YEL: i16 = 4;
-pub static CPAIR_BLU: i16 = 5;
-pub static CPAIR_MAG: i16 = 6;
-pub static CPAIR_CYA: i16 = 7;
-pub static CPAIR_WHI: i16 = 8;
-
-// Default pairs (color over BKG)
-pub static CPAIR_IBLA: i16 = 9;
-pub static CPAIR_IRED: i16 = 10;
-pub static CPAIR_IGRE: i16 = 11;
-pub static CPAIR_IYEL: i16 = 12;
-pub static CPAIR_IBLU: i16 = 13;
-pub static CPAIR_IMAG: i16 = 14;
-pub static CPAIR_ICYA: i16 = 15;
-pub static CPAIR_IWHI: i16 = 16;
-
-pub static CPAIR_DEFAULT: i16 = 17;
-
-// Custom colors 1-8 are already taken by default
-pub static COLOR_C1: i16 = 9;
-pub static COLOR_C2: i16 = 10;
-
-// Custom pairs
-pub static CPAIR_CUST: i16 = 10;
-
-/// We keep the proportions of rgb values and apply the alpha value, thus, at equal alpha, (1,1,1),
-/// (250,250,250) and (899,899,899) are all the same colors (whitin rounding errors)
-pub fn custom_color(color: i16, alpha: f64, r: usize, g: usize, b: usize) -> i32 {
-    // ||1000, 1000, 1000||
-    let max = 1732.0508075688772f64;
-    let alpha = alpha * max;
-    let (nr, ng, nb) = {
-        let norm = ((r * r + g * g + b * b) as f64).sqrt();
-        (alpha * r as f64 / norm, alpha * g as f64/ norm, alpha * b as f64 / norm)
-    };
-    init_color(color, nr as i16, ng as i16, nb as i16)
-}
-
-pub fn create_custom_colors() {
-    custom_color(COLOR_C1, 1., 800, 400, 200);
-    custom_color(COLOR_C2, 0.1, 1000, 1000, 1000);
-}
-
-pub fn create_custom_pairs() {
-    init_pair(CPAIR_CUST,     COLOR_C1,   COLOR_C2);
-}
-
-pub fn init_color_set() {
-
-    init_pair(CPAIR_DEFAULT, COLOR_RED,     COLOR_BKG);
-
-    init_pair(CPAIR_BLA,     COLOR_BLACK,   COLOR_BKG);
-    init_pair(CPAIR_RED,     COLOR_RED,     COLOR_BKG);
-    init_pair(CPAIR_GRE,     COLOR_GREEN,   COLOR_BKG);
-    init_pair(CPAIR_YEL,     COLOR_YELLOW,  COLOR_BKG);
-    init_pair(CPAIR_BLU,     COLOR_BLUE,    COLOR_BKG);
-    init_pair(CPAIR_MAG,     COLOR_MAGENTA, COLOR_BKG);
-    init_pair(CPAIR_CYA,     COLOR_CYAN,    COLOR_BKG);
-    init_pair(CPAIR_WHI,     COLOR_WHITE,   COLOR_BKG);
-
-    init_pair(CPAIR_IBLA,    COLOR_BKG,     COLOR_BLACK  );
-    init_pair(CPAIR_IRED,    COLOR_BKG,     COLOR_RED    );
-    init_pair(CPAIR_IGRE,    COLOR_BKG,     COLOR_GREEN  );
-    init_pair(CPAIR_IYEL,    COLOR_BKG,     COLOR_YELLOW );
-    init_pair(CPAIR_IBLU,    COLOR_BKG,     COLOR_BLUE   );
-    init_pair(CPAIR_IMAG,    COLOR_BKG,     COLOR_MAGENTA);
-    init_pair(CPAIR_ICYA,    COLOR_BKG,     COLOR_CYAN   );
-    init_pair(CPAIR_IWHI,    COLOR_BKG,     COLOR_WHITE  );
-
-
-    create_custom_colors();
-    create_custom_pairs();
+use std::cell::RefCell;
+use std::collections::HashMap;
 
+use super::backend;
+use super::theme;
+
+// The standard colors
+pub const COLOR_BLACK: i16 = 0;
+pub const COLOR_RED: i16 = 1;
+pub const COLOR_GREEN: i16 = 2;
+pub const COLOR_YELLOW: i16 = 3;
+pub const COLOR_BLUE: i16 = 4;
+pub const COLOR_MAGENTA: i16 = 5;
+pub const COLOR_CYAN: i16 = 6;
+pub const COLOR_WHITE: i16 = 7;
+
+/// 8 bits per channel
+pub type Rgb = (u8, u8, u8);
+
+/// A color as asked for by drawing code, turned into one the terminal has by `pair`
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub enum Color {
+    /// The terminal's own text or background color
+    Default,
+    /// One of the 16 colors of the terminal's own palette, `COLOR_*` and their bright versions
+    Standard(i16),
+    Rgb(u8, u8, u8),
+}
+
+/// Color pair for the i-th series of a plot, from the theme's colors, cycling when there are
+/// more series than colors
+pub fn series_color(i: usize) -> i16 {
+    text_pair(theme::with(|t| t.series[i % t.series.len()]))
+}
+
+/// Rgb value of the i-th series color of the theme, for exports to match the screen
+pub fn series_rgb(i: usize) -> Rgb {
+    paper_rgb(theme::with(|t| t.series[i % t.series.len()]))
+}
+
+/// Color on paper, for exports. Standard colors are darker than the terminal's so they stand
+/// out on white.
+pub fn paper_rgb(color: Color) -> Rgb {
+    match color {
+        Color::Standard(COLOR_RED) => (0xd6, 0x27, 0x28),
+        Color::Standard(COLOR_GREEN) => (0x2c, 0xa0, 0x2c),
+        Color::Standard(COLOR_YELLOW) => (0xc8, 0x9b, 0x00),
+        Color::Standard(COLOR_BLUE) => (0x1f, 0x77, 0xb4),
+        Color::Standard(COLOR_MAGENTA) => (0xb0, 0x3a, 0xb8),
+        Color::Standard(COLOR_CYAN) => (0x17, 0xa2, 0xb8),
+        Color::Standard(COLOR_WHITE) => (0x60, 0x60, 0x60),
+        Color::Default | Color::Standard(_) => (0, 0, 0),
+        Color::Rgb(r, g, b) => (r, g, b),
+    }
+}
+
+/// How rgb colors can be shown on this terminal
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum ColorDepth {
+    /// No colors at all, everything is drawn in pair 0
+    Mono,
+    /// Only the 8 standard colors
+    Basic,
+    /// The xterm 256 color palette, fixed
+    Xterm256,
+    /// Colors past the first 16 can be given any rgb value
+    TrueColor,
+}
+
+/// Hands out color pairs on demand, and colors for rgb values when the terminal can take them
+struct Manager {
+    depth: ColorDepth,
+    colors: i32,
+    /// Pair numbers given out, by text and background colors as numbered by the terminal
+    pairs: HashMap<(i16, i16), i16>,
+    /// Highest pair number that can be given out
+    last_pair: i16,
+    /// Colors given an rgb value, past the standard 16
+    defined: HashMap<Rgb, i16>,
+}
+
+impl Manager {
+
+    fn new() -> Manager {
+        let (colors, pairs, can_change) = backend::with(|b| (b.colors(), b.color_pairs(), b.can_change_color()));
+        let depth = match colors {
+            n if n < 8 => ColorDepth::Mono,
+            n if n > 16 && can_change => ColorDepth::TrueColor,
+            n if n >= 256 => ColorDepth::Xterm256,
+            _ => ColorDepth::Basic,
+        };
+        // Pair numbers past 255 don't fit in the attributes
+        let last_pair = (pairs.min(256) - 1).max(0) as i16;
+        Manager { depth, colors, pairs: HashMap::new(), last_pair, defined: HashMap::new() }
+    }
+
+    /// The terminal's number for `color`, the closest one it has for rgb values
+    fn color(&mut self, color: Color) -> i16 {
+        let rgb = match color {
+            Color::Default => return -1,
+            Color::Standard(c) if self.colors > 8 || c < 8 => return c,
+            Color::Standard(c) => return c - 8,
+            Color::Rgb(r, g, b) => (r, g, b),
+        };
+        match self.depth {
+            ColorDepth::TrueColor => {
+                if let Some(&c) = self.defined.get(&rgb) { return c }
+                let c = 16 + self.defined.len() as i32;
+                if c < self.colors.min(i16::MAX as i32) {
+                    backend::with(|b| b.init_color(c as i16, rgb));
+                    self.defined.insert(rgb, c as i16);
+                    c as i16
+                }
+                // Out of colors to define. Slots of the xterm palette may have been given other
+                // values by now, the standard 16 are left as they were.
+                else { nearest_basic(rgb) }
+            },
+            ColorDepth::Xterm256 => xterm256(rgb),
+            ColorDepth::Basic | ColorDepth::Mono => nearest_basic(rgb),
+        }
+    }
+
+    fn pair(&mut self, fg: Color, bg: Color) -> i16 {
+        if self.depth == ColorDepth::Mono { return 0 }
+        let key = (self.color(fg), self.color(bg));
+        if key == (-1, -1) { return 0 }
+        if let Some(&p) = self.pairs.get(&key) { return p }
+        let p = self.pairs.len() as i16 + 1;
+        if p > self.last_pair { return 0 }
+        backend::with(|b| b.init_pair(p, key.0, key.1));
+        self.pairs.insert(key, p);
+        p
+    }
+
+}
+
+thread_local! {
+    static MANAGER: RefCell<Option<Manager>> = const { RefCell::new(None) };
+}
+
+fn with_manager<R>(f: impl FnOnce(&mut Manager) -> R) -> R {
+    MANAGER.with(|m| f(m.borrow_mut().get_or_insert_with(Manager::new)))
+}
+
+/// Starts giving out pairs and colors afresh, for the backend just installed
+pub fn init() {
+    MANAGER.with(|m| *m.borrow_mut() = Some(Manager::new()));
+}
+
+/// Pair drawing `fg` over `bg`, set up the first time it is asked for. Pair 0, the terminal's
+/// default colors, without color support or once all pairs are taken.
+pub fn pair(fg: Color, bg: Color) -> i16 {
+    with_manager(|m| m.pair(fg, bg))
+}
+
+/// Pair drawing `color` over the theme's background
+pub fn text_pair(color: Color) -> i16 {
+    pair(color, theme::with(|t| t.background))
+}
+
+pub fn color_depth() -> ColorDepth {
+    with_manager(|m| m.depth)
+}
+
+/// Text and background colors of a pair, -1 for the terminal defaults
+pub fn pair_colors(cpair: i16) -> (i16, i16) {
+    backend::with(|b| b.pair_colors(cpair))
+}
+
+/// Rgb value of a color past the standard 16, when it was given one
+pub fn color_rgb(color: i16) -> Option<Rgb> {
+    backend::with(|b| b.color_rgb(color))
+}
+
+fn distance((r1, g1, b1): Rgb, (r2, g2, b2): Rgb) -> i32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// Closest entry of the xterm 256 palette, either in the 6x6x6 cube or the gray ramp
+pub fn xterm256((r, g, b): Rgb) -> i16 {
+    const STEPS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let nearest = |c: u8| (0..6).min_by_key(|i| (STEPS[*i] as i32 - c as i32).abs()).unwrap_or(0);
+    let (ri, gi, bi) = (nearest(r), nearest(g), nearest(b));
+    let cube = (STEPS[ri], STEPS[gi], STEPS[bi]);
+
+    let mean = (r as i32 + g as i32 + b as i32) / 3;
+    let level = ((mean - 8).max(0) / 10).min(23);
+    let gray = (8 + level * 10) as u8;
+
+    if distance((gray, gray, gray), (r, g, b)) < distance(cube, (r, g, b)) {
+        232 + level as i16
+    }
+    else {
+        16 + 36 * ri as i16 + 6 * gi as i16 + bi as i16
+    }
 }
 
+/// Closest of the 8 standard colors by hue, grays going to black or white. Distances in rgb
+/// would turn most mid tones into white or dim yellow, whatever their hue.
+pub fn nearest_basic((r, g, b): Rgb) -> i16 {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    if max < 96. { return COLOR_BLACK }
+    if max - min < 48. { return if max + min > 255. { COLOR_WHITE } else { COLOR_BLACK } }
+
+    let chroma = max - min;
+    let hue = if max == r { (g - b) / chroma }
+        else if max == g { (b - r) / chroma + 2. }
+        else { (r - g) / chroma + 4. };
+    // Sixths of the color wheel, centered on red, yellow, green...
+    match ((hue * 60. + 30.).rem_euclid(360.) / 60.) as i32 {
+        0 => COLOR_RED,
+        1 => COLOR_YELLOW,
+        2 => COLOR_GREEN,
+        3 => COLOR_CYAN,
+        4 => COLOR_BLUE,
+        _ => COLOR_MAGENTA,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::draw::backend::memory::Memory;
+    use crate::draw::windows::YX;
+
+    fn install(colors: i32, can_change: bool) -> Memory {
+        let screen = Memory::with_colors(YX(1, 1), colors, can_change);
+        backend::install(Box::new(screen.clone()));
+        init();
+        screen
+    }
+
+    #[test]
+    fn pairs_are_given_once() {
+        install(256, false);
+        let green = text_pair(Color::Rgb(0x5f, 0xd7, 0x5f));
+        let red = text_pair(Color::Standard(COLOR_RED));
+        assert_eq!((green, red), (1, 2));
+        assert_eq!(text_pair(Color::Rgb(0x5f, 0xd7, 0x5f)), green);
+        // Maps to the same palette entry as the first green
+        assert_eq!(text_pair(Color::Rgb(0x60, 0xd8, 0x60)), green);
+        assert_eq!(pair_colors(green), (77, COLOR_BLACK));
+        assert_eq!(pair(Color::Standard(COLOR_BLACK), Color::Standard(COLOR_RED)), 3);
+        assert_eq!(pair(Color::Default, Color::Default), 0);
+    }
+
+    #[test]
+    fn rgb_colors_by_depth() {
+        let screen = install(256, true);
+        assert_eq!(color_depth(), ColorDepth::TrueColor);
+        let p = text_pair(Color::Rgb(1, 2, 3));
+        assert_eq!(pair_colors(p), (16, COLOR_BLACK));
+        assert_eq!(color_rgb(16), Some((1, 2, 3)));
+        drop(screen);
+
+        // Once the colors to define run out, the standard ones rather than redefined slots
+        install(18, true);
+        assert_eq!(pair_colors(text_pair(Color::Rgb(1, 2, 3))).0, 16);
+        assert_eq!(pair_colors(text_pair(Color::Rgb(4, 5, 6))).0, 17);
+        assert_eq!(pair_colors(text_pair(Color::Rgb(0xf0, 0x55, 0x55))).0, COLOR_RED);
+
+        install(8, false);
+        assert_eq!(color_depth(), ColorDepth::Basic);
+        // Bright colors fall back to the standard ones
+        assert_eq!(pair_colors(text_pair(Color::Standard(COLOR_RED + 8))), (COLOR_RED, COLOR_BLACK));
+        assert_eq!(pair_colors(series_color(4)), (COLOR_BLUE, COLOR_BLACK));
+
+        install(0, false);
+        assert_eq!(series_color(0), 0);
+    }
+
+    #[test]
+    fn series_hues_stay_apart_on_basic_terminals() {
+        for theme in &[theme::Theme::dark(), theme::Theme::light()] {
+            let basic: Vec<_> = theme.series.iter().map(|c| match *c {
+                Color::Rgb(r, g, b) => nearest_basic((r, g, b)),
+                _ => -1,
+            }).collect();
+            assert_eq!(basic, [COLOR_GREEN, COLOR_CYAN, COLOR_YELLOW, COLOR_MAGENTA, COLOR_BLUE, COLOR_RED], "{}", theme.name);
+        }
+        assert_eq!(nearest_basic((0x30, 0x30, 0x30)), COLOR_BLACK);
+        assert_eq!(nearest_basic((0xd0, 0xd0, 0xc0)), COLOR_WHITE);
+    }
+}
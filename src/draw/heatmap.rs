@@ -0,0 +1,213 @@
+use crate::axis::Axis;
+use crate::fft::{Spectrogram, WindowFunction};
+use crate::stream::{Frame, Matrix};
+use crate::yrange::YRange;
+
+use super::attrs::color_pair;
+use super::colors::{*};
+use super::plot::{self, PlotArea};
+use super::theme;
+use super::windows::{*};
+
+/// Steps of a color scale, each one a color pair
+const LEVELS: usize = 16;
+
+/// Shades standing in for colors on terminals with only the 8 standard ones
+const SHADES: [&str; 4] = ["░", "▒", "▓", "█"];
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Palette {
+    Viridis,
+    Magma,
+    Grayscale,
+}
+
+impl Palette {
+
+    pub fn from(s: &str) -> Option<Palette> {
+        match s {
+            "viridis" => Some(Palette::Viridis),
+            "magma" => Some(Palette::Magma),
+            "gray" | "grayscale" => Some(Palette::Grayscale),
+            _ => None
+        }
+    }
+
+    pub fn next(self) -> Palette {
+        match self {
+            Palette::Viridis => Palette::Magma,
+            Palette::Magma => Palette::Grayscale,
+            Palette::Grayscale => Palette::Viridis,
+        }
+    }
+
+    /// Evenly spaced samples of the scale, interpolated in between
+    fn stops(self) -> &'static [(u8, u8, u8)] {
+        match self {
+            Palette::Viridis => &[
+                (0x44, 0x01, 0x54), (0x48, 0x28, 0x78), (0x3e, 0x4a, 0x89), (0x31, 0x68, 0x8e),
+                (0x26, 0x82, 0x8e), (0x1f, 0x9e, 0x89), (0x35, 0xb7, 0x79), (0x6d, 0xcd, 0x59),
+                (0xb4, 0xde, 0x2c), (0xfd, 0xe7, 0x25),
+            ],
+            Palette::Magma => &[
+                (0x00, 0x00, 0x04), (0x18, 0x0f, 0x3d), (0x44, 0x0f, 0x76), (0x72, 0x1f, 0x81),
+                (0x9e, 0x2f, 0x7f), (0xcd, 0x40, 0x71), (0xf1, 0x60, 0x5d), (0xfd, 0x96, 0x68),
+                (0xfe, 0xca, 0x8d), (0xfc, 0xfd, 0xbf),
+            ],
+            Palette::Grayscale => &[(0, 0, 0), (255, 255, 255)],
+        }
+    }
+
+    /// Color at `t`, from 0 (low end) to 1 (high end)
+    pub fn rgb(self, t: f32) -> (u8, u8, u8) {
+        let stops = self.stops();
+        let pos = t.clamp(0., 1.) * (stops.len() - 1) as f32;
+        let i = (pos.floor() as usize).min(stops.len() - 2);
+        let f = pos - i as f32;
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+        let ((r1, g1, b1), (r2, g2, b2)) = (stops[i], stops[i + 1]);
+        (mix(r1, r2), mix(g1, g2), mix(b1, b2))
+    }
+
+}
+
+/// A palette loaded into the terminal as color pairs, a pair per level. The colors are the
+/// exact ones or the closest the terminal has, and on 8 color terminals levels also get a
+/// denser or lighter shade.
+pub struct Colormap {
+    pairs: Vec<i16>,
+    shaded: bool,
+}
+
+impl Colormap {
+
+    pub fn init(palette: Palette) -> Colormap {
+        let shaded = color_depth() == ColorDepth::Basic;
+        let pairs = (0..LEVELS).map(|level| {
+            let (r, g, b) = palette.rgb(level as f32 / (LEVELS - 1) as f32);
+            if shaded { text_pair(Color::Rgb(r, g, b)) } else { pair(theme::with(|t| t.background), Color::Rgb(r, g, b)) }
+        }).collect();
+        Colormap { pairs, shaded }
+    }
+
+    /// Glyph and color pair for a value normalized to [0, 1]
+    pub fn cell(&self, t: f32) -> (&'static str, i16) {
+        let level = (t.clamp(0., 1.) * (LEVELS - 1) as f32).round() as usize;
+        let glyph = if self.shaded { SHADES[level * SHADES.len() / LEVELS] } else { " " };
+        (glyph, self.pairs[level])
+    }
+
+}
+
+/// Fills the plot area with the matrix, stretched or squeezed to fit. Values are mapped to the
+/// colormap between `lo` and `hi`; NaNs are left blank.
+pub fn draw_heatmap(window: &mut Window, area: PlotArea, matrix: &Matrix, colormap: &Colormap, lo: f32, hi: f32) -> NcResult {
+    let (cols, rows) = matrix.size();
+    if cols == 0 || rows == 0 { return Ok(0) }
+    let span = if hi > lo { hi - lo } else { 1. };
+
+    for x in 0..area.cols as usize {
+        for y in 0..area.rows as usize {
+            let v = matrix.get(x * cols / area.cols as usize, y * rows / area.rows as usize);
+            if v.is_nan() { continue }
+            let (glyph, pair) = colormap.cell((v - lo) / span);
+            if let Some(yx) = area.cell(x, y) {
+                window.wattron(color_pair(pair))?;
+                window.mvwprintw(yx, glyph)?;
+                window.wattroff(color_pair(pair))?;
+            }
+        }
+    }
+    Ok(0)
+}
+
+/// `lo ▕colors▏ hi` ending at column `right` of row `y`
+pub fn draw_colorbar(window: &mut Window, y: i32, right: i32, colormap: &Colormap, lo: &str, hi: &str) -> NcResult {
+    let width = LEVELS as i32;
+    let x = right - width - hi.len() as i32 - 1;
+    window.mvwprintw(YX(y, x - lo.len() as i32 - 1), lo)?;
+    for level in 0..LEVELS {
+        let (glyph, pair) = colormap.cell(level as f32 / (LEVELS - 1) as f32);
+        window.wattron(color_pair(pair))?;
+        window.mvwprintw(YX(y, x + level as i32), glyph)?;
+        window.wattroff(color_pair(pair))?;
+    }
+    window.mvwprintw(YX(y, x + width + 1), hi)
+}
+
+/// How the values of a frame spread over time: each column is a time (or index) bucket and
+/// each row a value bin, colored by how many samples fell in it.
+pub fn plot_distribution(w: &mut ScaledWindow, name: &str, frame: &Frame<f32>, colormap: &Colormap, yrange: &YRange) -> NcResult {
+    let area = PlotArea::of(&w.window);
+    if area.is_empty() { return Ok(0) }
+
+    let (vmin, vmax) = yrange.fit(frame.min() as f64, frame.max() as f64, Axis::Linear);
+    let vscale = (vmax - vmin) / (area.rows - 1) as f64;
+    let (hoffset, hspan) = plot::x_extent(frame);
+    let hscale = if hspan > 0. { hspan / (area.cols - 1) as f64 } else { 1. };
+
+    w.set_scale_offset(vscale, vmin, hscale, hoffset);
+    w.set_axes(plot::time_axis(frame), Axis::Linear);
+    plot::draw_axes(w, area, vmax, hoffset + hspan)?;
+
+    let t0 = frame.time_span().map(|(t0, _)| t0);
+    let counts = frame.raster_at(vscale as f32, hscale as f32, vmin as f32, t0).matrix();
+    // Cell for cell with the plot, trailing rows or columns without samples stay at zero
+    let mut matrix = Matrix::new(area.cols as usize, area.rows as usize);
+    let (cols, rows) = counts.size();
+    for x in 0..cols.min(area.cols as usize) {
+        for y in 0..rows.min(area.rows as usize) {
+            matrix.set(x, y, counts.get(x, y));
+        }
+    }
+    let (_, most) = matrix.range();
+    draw_heatmap(&mut w.window, area, &matrix, colormap, 0., most)?;
+
+    plot::draw_legend(&mut w.window, area, &[(name, theme::pair(|t| t.text))])?;
+    let YX(_, width) = w.window.shape().size;
+    draw_colorbar(&mut w.window, 0, width - 2, colormap, "0", &format!("{}", most))
+}
+
+/// Decibels below the loudest bin still given a color in the spectrogram
+const DB_RANGE: f32 = 60.;
+
+/// Short-time spectra of the latest samples, scrolling left as the frame grows: time on x,
+/// frequency on y, power in color. Windows of `size` samples overlap by half.
+pub fn plot_spectrogram(w: &mut ScaledWindow, name: &str, frame: &Frame<f32>, rate: f64,
+                        window: WindowFunction, size: usize, colormap: &Colormap) -> NcResult {
+    let area = PlotArea::of(&w.window);
+    if area.is_empty() { return Ok(0) }
+
+    let hop = size / 2;
+    let (ini, _) = frame.range();
+    let gram = Spectrogram::of(frame.data(), ini, rate, window, size, hop, area.cols as usize);
+    if gram.is_empty() { return plot::draw_legend(&mut w.window, area, &[(name, theme::pair(|t| t.text))]) }
+
+    // x of each column: the time, or the stream index, at the middle of its window
+    let center = |start: usize| match frame.time() {
+        Some(time) => time[(start + size / 2).min(time.len() - 1)],
+        None => (ini + start + size / 2) as f64,
+    };
+    let (first, last) = (gram.starts[0], gram.starts[gram.starts.len() - 1]);
+    let hmax = center(last);
+    let (cols, _) = gram.matrix.size();
+    // A short history is stretched over the plot, a long one scrolls a column per window
+    let hspan = if cols < area.cols as usize {
+        hmax - center(first)
+    } else {
+        (hmax - center(first)) * (area.cols - 1) as f64 / (cols - 1) as f64
+    };
+    let hscale = if hspan > 0. { hspan / (area.cols - 1) as f64 } else { 1. };
+    let hoffset = hmax - hspan;
+    w.set_scale_offset(gram.nyquist / area.rows as f64, 0., hscale, hoffset);
+    w.set_axes(plot::time_axis(frame), Axis::Linear);
+    plot::draw_axes(w, area, gram.nyquist, hmax)?;
+
+    let (_, hi) = gram.matrix.range();
+    let lo = hi - DB_RANGE;
+    draw_heatmap(&mut w.window, area, &gram.matrix, colormap, lo, hi)?;
+
+    plot::draw_legend(&mut w.window, area, &[(name, theme::pair(|t| t.text))])?;
+    let YX(_, width) = w.window.shape().size;
+    draw_colorbar(&mut w.window, 0, width - 2, colormap, &format!("{:.0}", lo), &format!("{:.0} dB", hi.round() + 0.))
+}
@@ -0,0 +1,18 @@
+// Codes of the keys without a character of their own, as given by `poll_input`. They are the
+// ones of ncurses, other backends translate to them.
+
+// Only translated to by the crossterm backend, ncurses hands out its own codes for them
+#[cfg_attr(not(feature = "crossterm"), allow(dead_code))]
+pub const KEY_DOWN: i32 = 0x102;
+#[cfg_attr(not(feature = "crossterm"), allow(dead_code))]
+pub const KEY_UP: i32 = 0x103;
+#[cfg_attr(not(feature = "crossterm"), allow(dead_code))]
+pub const KEY_LEFT: i32 = 0x104;
+#[cfg_attr(not(feature = "crossterm"), allow(dead_code))]
+pub const KEY_RIGHT: i32 = 0x105;
+/// Function key n is `KEY_F0 + n`
+#[cfg_attr(not(feature = "crossterm"), allow(dead_code))]
+pub const KEY_F0: i32 = 0x108;
+
+pub const KEY_BACKSPACE: i32 = 0x107;
+pub const KEY_ENTER: i32 = 0x157;
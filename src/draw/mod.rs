@@ -1,11 +1,22 @@
-use ncurses::*;
-use std::char;
 use std::str::{FromStr};
 use std::fmt::Debug;
+use std::io;
 
+pub mod attrs;
+pub mod backend;
+pub mod keys;
 pub mod windows;
 pub mod colors;
-
+pub mod plot;
+pub mod heatmap;
+pub mod sparkline;
+pub mod stats;
+pub mod theme;
+#[cfg(test)]
+mod snapshot;
+
+use attrs::color_pair;
+use backend::Input;
 use windows::{*};
 // use windows::{NcursesWindow, NcursesWindowParent};
 
@@ -21,77 +32,74 @@ pub enum DrawError {
 //     }
 // }
 
-pub fn start_ncurses_mode() {
-    // utf-8 support
-    setlocale(LcCategory::all, "");
-
-    initscr();
-    raw();
-    keypad(stdscr(), true);
-    noecho();
-    curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
-    if has_colors() == true {
-        // mvprintw(0,0,&format!("COLOR"));
-        start_color();
-        colors::init_color_set();
-    }
+/// Takes over the terminal for drawing
+pub fn start_terminal() -> io::Result<()> {
+    backend::install(backend::terminal()?);
+    theme::apply();
+    Ok(())
 }
 
-pub fn end_ncurses_mode() {
-    endwin();
+/// Gives the terminal back
+pub fn end_terminal() {
+    backend::with(|b| b.end());
 }
 
-pub fn main_window<'a>() -> ScaledWindow {
-    // This should be a ScaleWindow, a SimpleWindow with additional attributes, like scale,
-    // offset, units, tick frequency
-    let screen = windows::screen_size();
-    let shape = Shape { pos: YX(0, 2), size: screen - YX(1,2) };
-    let mut w = windows::ScaledWindow::new(shape, None);
-    let style = Style::from(0, ' ' as chtype,
-                            ' ' as chtype, 0,
-                            ' ' as chtype, ' ' as chtype,
-                            0, ' ' as chtype);
-    w.window.wborder(style);
-    w.window.wrefresh();
-    w
+/// Visual bell, the whole screen blinks once
+pub fn flash_screen() {
+    backend::with(|b| b.flash());
 }
 
-pub fn create_subwindow<'a>(w: &'a mut Window) -> windows::NcResult { //-> &mut SimpleWindow {
-
-    let shape = Shape { pos: YX(10, 10), size: YX(5,5) };
-    w.subwin(&shape, "Win1");
-    w.draw_sw("Win1", |child| {
-        let style = Style::default();
-        child.wresize(5, 20)?;
-        child.wborder(style)?;
-        child.mvwprintw(YX(1,1), "Win1")
-    })?;
-
-    let shape = Shape { pos: YX(20, 10), size: YX(10,10) };
-    w.subwin(&shape, "Win2");
-    w.draw_sw("Win2", |child| {
-
-        child.mvwprintw(YX(1,1), "Win2")?;
-        child.wnoutrefresh()?;
-
-        child.wresize(15, 40)?;
-        child.wnoutrefresh()?;
-
-        child.mvwin(20, 40)?;
-        child.wnoutrefresh()?;
+/// Makes `poll_input` give up after `ms` milliseconds instead of blocking
+pub fn input_timeout(ms: i32) {
+    backend::with(|b| b.set_input_timeout(ms));
+}
 
-        child.wborder(Style::default())?;
-        child.wnoutrefresh()
-    })?;
+/// Next key or click, None when the input timeout ran out first
+pub fn poll_input() -> Option<Input> {
+    backend::with(|b| b.poll_input())
+}
 
-    // let code = w.delete_sw("Win1");
-    // println!("{:?}", code);
-    // code
-    Ok(0)
+/// Writes `text` on the bottom line of the screen, below the main window, in color pair
+/// `cpair` if given or else in the theme's highlight color. The cursor is shown at its end
+/// while the user types into it.
+pub fn status_line(text: &str, cursor: bool, cpair: Option<i16>) {
+    let YX(height, width) = windows::screen_size();
+    // Up to the last column, writing there could scroll the screen
+    let text: String = text.chars().take((width - 1).max(0) as usize).collect();
+    let len = text.chars().count() as i32;
+    let attrs = color_pair(cpair.unwrap_or_else(|| theme::pair(|t| t.highlight)));
+    backend::with(|b| {
+        b.print(YX(height - 1, 0), &" ".repeat((width - 1).max(0) as usize));
+        b.set_attrs(attrs);
+        b.print(YX(height - 1, 0), &text);
+        b.set_attrs(0);
+        b.set_cursor(if cursor { Some(YX(height - 1, len)) } else { None });
+        b.flush();
+    });
+}
 
+/// Contents of the screen as last shown, above the status line
+pub fn screen_cells() -> Vec<Vec<backend::Cell>> {
+    let mut cells = backend::with(|b| b.cells());
+    cells.pop();
+    cells
 }
 
-pub fn sub_window<'a>(w: &'a mut Window) { //-> &mut SimpleWindow {
+/// The plot window, leaving `right` columns free for a side panel
+pub fn main_window(right: i32) -> ScaledWindow {
+    // This should be a ScaleWindow, a SimpleWindow with additional attributes, like scale,
+    // offset, units, tick frequency
     let screen = windows::screen_size();
-    create_subwindow(w);
+    let shape = Shape { pos: YX(0, 2), size: screen - YX(1, 2 + right) };
+    let mut w = windows::ScaledWindow::new(shape, None);
+    // Blank border, the plot draws its own axes
+    let _ = w.window.wborder(Style::blank());
+    w
+}
+
+/// Boxed window `width` columns wide along the right edge, above the status line
+pub fn side_panel(width: i32) -> Window {
+    let YX(height, cols) = windows::screen_size();
+    let shape = Shape { pos: YX(0, cols - width), size: YX(height - 1, width) };
+    Window::new(shape, None)
 }
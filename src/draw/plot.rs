@@ -0,0 +1,549 @@
+use crate::axis::Axis;
+use crate::fft::Spectrum;
+use crate::stream::{Frame, FramePair, Histogram, Raster};
+use crate::threshold::{Level, Limit, Threshold};
+use crate::yrange::YRange;
+
+use super::attrs::color_pair;
+use super::colors::{*};
+use super::theme;
+use super::windows::{*};
+
+/// Columns on the left of the plot reserved for the y labels
+const LABEL_WIDTH: i32 = 10;
+
+/// Cells of a window the data is drawn on, the axes and their labels go around it
+#[derive(Copy,Clone,Debug)]
+pub struct PlotArea {
+    pub top: i32,
+    pub left: i32,
+    pub rows: i32,
+    pub cols: i32,
+}
+
+impl PlotArea {
+
+    /// Leaves the first row for the legend, the last two for the x axis and its labels
+    pub fn of(window: &Window) -> PlotArea {
+        let YX(height, width) = window.shape().size;
+        PlotArea {
+            top: 1,
+            left: LABEL_WIDTH,
+            rows: height - 3 - 1,
+            cols: width - LABEL_WIDTH - 1,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows < 2 || self.cols < 2
+    }
+
+    /// Window position of the raster cell (column, row), rows counted from the bottom
+    pub fn cell(&self, x: usize, y: usize) -> Option<YX> {
+        let (x, y) = (x as i32, y as i32);
+        if x < self.cols && y < self.rows {
+            Some(YX(self.top + self.rows - 1 - y, self.left + x))
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.top + self.rows - 1
+    }
+}
+
+/// Data ranges of the axes last drawn into a window, what exports need to reproduce the plot
+#[derive(Copy,Clone,Debug)]
+pub struct Scales {
+    pub haxis: Axis,
+    pub vaxis: Axis,
+    /// Left and right ends of the x axis
+    pub x: (f64, f64),
+    /// Bottom and top of the value axis
+    pub y: (f64, f64),
+    /// Cells of the screen the data was drawn on
+    pub area: Shape,
+}
+
+impl Scales {
+
+    pub fn of(w: &ScaledWindow) -> Scales {
+        let area = PlotArea::of(&w.window);
+        let (haxis, vaxis) = (w.haxis(), w.vaxis());
+        let right = w.hoffset() + w.hscale() * (area.cols - 1) as f64;
+        let top = w.voffset() + w.vscale() * (area.rows - 1) as f64;
+        Scales {
+            haxis,
+            vaxis,
+            x: (haxis.inverse(w.hoffset()), haxis.inverse(right)),
+            y: (vaxis.inverse(w.voffset()), vaxis.inverse(top)),
+            area: Shape { pos: w.window.shape().pos + YX(area.top, area.left), size: YX(area.rows, area.cols) },
+        }
+    }
+
+    /// Values of the axes at a cell of the screen, as labels precise to a cell, when the cell
+    /// is on the plot
+    pub fn readout(&self, YX(y, x): YX) -> Option<(String, String)> {
+        let Shape { pos: YX(top, left), size: YX(rows, cols) } = self.area;
+        let (col, row) = (x - left, top + rows - 1 - y);
+        if col < 0 || col >= cols || row < 0 || row >= rows { return None }
+        let label = |axis: Axis, (lo, hi): (f64, f64), i: i32, n: i32| {
+            let (lo, hi) = (axis.forward(lo), axis.forward(hi));
+            let step = (hi - lo) / (n - 1).max(1) as f64;
+            axis.label(axis.inverse(lo + step * i as f64), step)
+        };
+        Some((label(self.haxis, self.x, col, cols), label(self.vaxis, self.y, row, rows)))
+    }
+
+}
+
+/// Draws every frame over the same axes, each labeled with its series name. The x axis is the
+/// sample index, or wall-clock time when the frames carry timestamps; timed frames are expected
+/// to cover the same time span. Values are placed on `vaxis`, linear, log or symlog.
+pub fn plot_frames(w: &mut ScaledWindow, series: &[(&str, Frame<f32>)], vaxis: Axis, yrange: &YRange,
+                   thresholds: &[Threshold]) -> NcResult {
+    let area = PlotArea::of(&w.window);
+    if series.is_empty() || area.is_empty() { return Ok(0) }
+    let frames = || series.iter().map(|(_, frame)| frame);
+
+    // Bottom and top in the coordinate where the axis is linear
+    let (lo, hi) = match vaxis {
+        Axis::Linear => value_span(frames()),
+        _ => forward_span(frames(), vaxis),
+    };
+    let (lo, hi) = yrange.fit(lo, hi, vaxis);
+    let vscale = (hi - lo) / (area.rows - 1) as f64;
+
+    let first = &series[0].1;
+    let (hoffset, hspan) = x_extent(first);
+    let hscale = if hspan > 0. { hspan / (area.cols - 1) as f64 } else { 1. };
+
+    w.set_scale_offset(vscale, lo, hscale, hoffset);
+    w.set_axes(time_axis(first), vaxis);
+    draw_axes(w, area, vaxis.inverse(hi), hoffset + hspan)?;
+
+    draw_thresholds(w, area, thresholds)?;
+    let t0 = first.time_span().map(|(t0, _)| t0);
+    for (i, (name, frame)) in series.iter().enumerate() {
+        let raster = frame.raster_on(vaxis, vscale as f32, hscale as f32, lo as f32, t0);
+        // Rows of the limits watching this series, compared with the exact sample heights
+        let limits: Vec<_> = thresholds.iter()
+            .filter(|t| t.applies_to(name))
+            .map(|t| match t.limit {
+                Limit::Above(v) | Limit::Below(v) => (t, w.row_of(v) as f32),
+                Limit::At(_) => (t, f32::NAN),
+            })
+            .collect();
+        let cpair = |h: f32| {
+            let out = limits.iter().filter(|(t, row)| match t.limit {
+                Limit::Above(_) => h > *row,
+                Limit::Below(_) => h < *row,
+                Limit::At(_) => false,
+            });
+            if out.clone().any(|(t, _)| t.level == Level::Critical) { level_color(Level::Critical) }
+            else if out.clone().next().is_some() { level_color(Level::Warning) }
+            else { series_color(i) }
+        };
+        draw_raster_by(&mut w.window, area, &raster, cpair)?;
+    }
+    draw_threshold_labels(w, area, thresholds)?;
+    draw_legend(&mut w.window, area, &legend(series.iter().map(|(name, _)| *name)))
+}
+
+/// Where the x axis of a frame starts and how long it is: a time span for timed frames, a
+/// number of samples otherwise
+pub fn x_extent(frame: &Frame<f32>) -> (f64, f64) {
+    match frame.time_span() {
+        Some((t0, t1)) => (t0, t1 - t0),
+        None => {
+            let (ini, end) = frame.range();
+            (ini as f64, (end - ini).saturating_sub(1) as f64)
+        },
+    }
+}
+
+/// Time axis for timed frames, a plain one counting samples otherwise
+pub fn time_axis(frame: &Frame<f32>) -> Axis {
+    if frame.time().is_some() { Axis::Time } else { Axis::Linear }
+}
+
+/// Lower block elements, a bar top in eighths of a cell
+const BLOCKS: [&str; 8] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇"];
+
+/// The frame as vertical bars, one per column, drawn with 1/8 of a cell precision. Bars stand
+/// on zero when all values are positive, as is the case for counts, else on the minimum.
+pub fn plot_bars(w: &mut ScaledWindow, name: &str, frame: &Frame<f32>, cpair: i16, yrange: &YRange) -> NcResult {
+    let area = PlotArea::of(&w.window);
+    if area.is_empty() { return Ok(0) }
+
+    let (min, max) = value_span(std::iter::once(frame));
+    let (vmin, vmax) = yrange.fit(if min >= 0. { 0. } else { min }, max, Axis::Linear);
+    // No padding under bars standing on zero
+    let vmin = if min >= 0. && vmin < 0. { 0. } else { vmin };
+    // Unlike points, a full bar takes the whole top row
+    let vscale = (vmax - vmin) / area.rows as f64;
+    let (hoffset, hspan) = x_extent(frame);
+    let mut hscale = if hspan > 0. { hspan / (area.cols - 1) as f64 } else { 1. };
+    // Short untimed frames get a column per sample rather than bars with gaps between them
+    if frame.time().is_none() { hscale = hscale.max(1.) }
+
+    w.set_scale_offset(vscale, vmin, hscale, hoffset);
+    w.set_axes(time_axis(frame), Axis::Linear);
+    draw_axes(w, area, vmax, hoffset + hspan)?;
+
+    let raster = frame.raster_at(vscale as f32, hscale as f32, vmin as f32, frame.time_span().map(|(t0, _)| t0));
+    draw_bars(&mut w.window, area, &raster.bars(), cpair)?;
+    draw_legend(&mut w.window, area, &[(name, cpair)])
+}
+
+/// Bars given in eighths of a cell, one per plot column
+pub fn draw_bars(window: &mut Window, area: PlotArea, bars: &[usize], cpair: i16) -> NcResult {
+    window.wattron(color_pair(cpair))?;
+    for (x, eighths) in bars.iter().enumerate() {
+        let (full, rest) = (eighths / 8, eighths % 8);
+        for y in 0..full {
+            if let Some(yx) = area.cell(x, y) { window.mvwprintw(yx, "█")?; }
+        }
+        if rest > 0 {
+            if let Some(yx) = area.cell(x, full) { window.mvwprintw(yx, BLOCKS[rest])?; }
+        }
+    }
+    window.wattroff(color_pair(cpair))
+}
+
+/// Magnitude against frequency, one bar per column holding the strongest bin that falls in it.
+/// A log frequency axis leaves out the zero bin.
+pub fn plot_spectrum(w: &mut ScaledWindow, name: &str, spectrum: &Spectrum, log_freq: bool, cpair: i16, yrange: &YRange) -> NcResult {
+    let area = PlotArea::of(&w.window);
+    if area.is_empty() || spectrum.is_empty() { return Ok(0) }
+
+    let haxis = if log_freq { Axis::Log } else { Axis::Linear };
+    let first = if log_freq { 1 } else { 0 };
+    let (fmin, fmax) = match (spectrum.freqs.get(first), spectrum.freqs.last()) {
+        (Some(lo), Some(hi)) if hi > lo => (*lo, *hi),
+        _ => return Ok(0),
+    };
+    let top = spectrum.max();
+    let (_, vmax) = yrange.fit(0., if top > 0. { top } else { 1. }, Axis::Linear);
+    let vscale = vmax / area.rows as f64;
+    let hoffset = haxis.forward(fmin);
+    let hscale = (haxis.forward(fmax) - hoffset) / (area.cols - 1) as f64;
+
+    w.set_scale_offset(vscale, 0., hscale, hoffset);
+    w.set_axes(haxis, Axis::Linear);
+    draw_axes(w, area, vmax, fmax)?;
+
+    let mut bars = vec![0; area.cols as usize];
+    for (f, m) in spectrum.freqs.iter().zip(&spectrum.magnitude).skip(first) {
+        let col = w.col_of(*f).round() as usize;
+        let eighths = (m / vscale * 8.).round() as usize;
+        if let Some(bar) = bars.get_mut(col) { *bar = (*bar).max(eighths) }
+    }
+    draw_bars(&mut w.window, area, &bars, cpair)?;
+    draw_legend(&mut w.window, area, &[(name, cpair)])
+}
+
+/// Glyphs for density shading, from a lone sample to the most crowded cell
+const DENSITY: [&str; 5] = ["·", "░", "▒", "▓", "█"];
+
+/// Each (x, y) pair of frames as a cloud of points, x against y. Both axes are linear and
+/// fitted to the data independently. With `density` crowded cells are drawn darker, instead of
+/// looking the same as a cell hit once.
+pub fn plot_scatter(w: &mut ScaledWindow, names: &[String], pairs: &[FramePair<f32>], density: bool) -> NcResult {
+    let area = PlotArea::of(&w.window);
+    if pairs.is_empty() || area.is_empty() { return Ok(0) }
+
+    let (xmin, xmax) = value_span(pairs.iter().map(|p| &p.0));
+    let (ymin, ymax) = value_span(pairs.iter().map(|p| &p.1));
+    let hscale = (xmax - xmin) / (area.cols - 1) as f64;
+    let vscale = (ymax - ymin) / (area.rows - 1) as f64;
+
+    w.set_scale_offset(vscale, ymin, hscale, xmin);
+    w.set_axes(Axis::Linear, Axis::Linear);
+    draw_axes(w, area, ymax, xmax)?;
+
+    for (i, (x, y)) in pairs.iter().enumerate() {
+        let raster = x.raster_xy(y, hscale as f32, vscale as f32, xmin as f32, ymin as f32);
+        if density {
+            draw_density(&mut w.window, area, &raster, series_color(i))?;
+        }
+        else {
+            draw_raster(&mut w.window, area, &raster, series_color(i))?;
+        }
+    }
+    draw_legend(&mut w.window, area, &legend(names.iter().map(String::as_str)))
+}
+
+/// Direction the bars of a histogram grow in
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Orientation {
+    Vertical,
+    Horizontal,
+}
+
+impl Orientation {
+    pub fn flip(self) -> Orientation {
+        match self {
+            Orientation::Vertical => Orientation::Horizontal,
+            Orientation::Horizontal => Orientation::Vertical,
+        }
+    }
+}
+
+/// Bars of a histogram, each labeled with its count. Vertical bars put the values on the x
+/// axis and the counts on the y axis, horizontal bars the other way round.
+pub fn plot_histogram(w: &mut ScaledWindow, name: &str, hist: &Histogram, orientation: Orientation, cpair: i16) -> NcResult {
+    let area = PlotArea::of(&w.window);
+    let n = hist.counts.len() as i32;
+    if n == 0 || area.is_empty() { return Ok(0) }
+    let most = hist.max_count().max(1) as f64;
+    let (lower, upper) = (hist.min as f64, hist.edges(hist.counts.len() - 1).1 as f64);
+
+    match orientation {
+        Orientation::Vertical => {
+            let bar = (area.cols / n).max(1);
+            // The top row is kept free for the count labels
+            let vscale = most / (area.rows - 2).max(1) as f64;
+            w.set_scale_offset(vscale, 0., hist.width as f64 / bar as f64, lower);
+            w.set_axes(Axis::Linear, Axis::Linear);
+            draw_axes(w, area, most, upper)?;
+
+            w.window.wattron(color_pair(cpair))?;
+            for (i, count) in hist.counts.iter().enumerate() {
+                let height = (*count as f64 / vscale).round() as i32;
+                let x = area.left + i as i32 * bar;
+                let fill = if bar >= 3 { bar - 1 } else { bar };
+                for row in 0..height {
+                    w.window.mvwprintw(YX(area.bottom() - row, x), &"█".repeat(fill as usize))?;
+                }
+                let label = count.to_string();
+                if label.len() as i32 <= bar {
+                    w.window.mvwprintw(YX(area.bottom() - height, x), &label)?;
+                }
+            }
+            w.window.wattroff(color_pair(cpair))?;
+        },
+        Orientation::Horizontal => {
+            let bar = (area.rows / n).max(1);
+            let fill = if bar >= 3 { bar - 1 } else { bar };
+            // Room at the right end of the longest bar for its label
+            let room = most.to_string().len() as i32 + 1;
+            let hscale = most / (area.cols - room).max(1) as f64;
+            w.set_scale_offset(hist.width as f64 / bar as f64, lower, hscale, 0.);
+            w.set_axes(Axis::Linear, Axis::Linear);
+            draw_axes(w, area, upper, most)?;
+
+            w.window.wattron(color_pair(cpair))?;
+            for (i, count) in hist.counts.iter().enumerate() {
+                let length = (*count as f64 / hscale).round() as i32;
+                let y = area.bottom() - i as i32 * bar;
+                for row in 0..fill {
+                    w.window.mvwprintw(YX(y - row, area.left), &"█".repeat(length as usize))?;
+                }
+                w.window.mvwprintw(YX(y - fill / 2, area.left + length + 1), &count.to_string())?;
+            }
+            w.window.wattroff(color_pair(cpair))?;
+        },
+    }
+    draw_legend(&mut w.window, area, &[(name, cpair)])
+}
+
+/// Smallest and largest value over all frames, widened when they are equal so the range can
+/// always be divided into rows
+fn value_span<'a, I>(frames: I) -> (f64, f64)
+    where I: Iterator<Item=&'a Frame<'a, f32>>
+{
+    let (min, max) = frames.fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), f| {
+        (min.min(f.min()), max.max(f.max()))
+    });
+    let (min, max) = (min as f64, max as f64);
+    if max > min { (min, max) } else { (min - 1., max + 1.) }
+}
+
+/// Smallest and largest value of the frames through `axis.forward`, leaving out values the
+/// axis can't show
+fn forward_span<'a, I>(frames: I, axis: Axis) -> (f64, f64)
+    where I: Iterator<Item=&'a Frame<'a, f32>>
+{
+    let (min, max) = frames
+        .flat_map(|f| f.data().iter())
+        .map(|v| axis.forward(*v as f64))
+        .filter(|u| u.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), u| (min.min(u), max.max(u)));
+    if max > min { (min, max) } else if max.is_finite() { (min - 1., max + 1.) } else { (0., 1.) }
+}
+
+/// Like `draw_raster`, with a color pair for each cell picked from its exact height
+pub fn draw_raster_by<F: Fn(f32) -> i16>(window: &mut Window, area: PlotArea, raster: &Raster, cpair: F) -> NcResult {
+    for ((x, y), h) in raster.bins().iter().zip(raster.heights()) {
+        if let Some(yx) = area.cell(*x, *y) {
+            let pair = cpair(*h);
+            window.wattron(color_pair(pair))?;
+            window.mvwprintw(yx, "•")?;
+            window.wattroff(color_pair(pair))?;
+        }
+    }
+    Ok(0)
+}
+
+/// Color thresholds of a level are drawn in
+pub fn level_hue(level: Level) -> Color {
+    match level {
+        Level::Warning => Color::Standard(COLOR_YELLOW),
+        Level::Critical => Color::Standard(COLOR_RED),
+    }
+}
+
+pub fn level_color(level: Level) -> i16 {
+    text_pair(level_hue(level))
+}
+
+/// Row of a horizontal threshold line, if it falls inside the plot
+fn threshold_row(w: &ScaledWindow, area: PlotArea, v: f64) -> Option<i32> {
+    let row = area.bottom() - w.row_of(v).round() as i32;
+    if row >= area.top && row <= area.bottom() { Some(row) } else { None }
+}
+
+/// Reference lines across the plot in the color of their level. Lines off the scales are
+/// left out.
+pub fn draw_thresholds(w: &mut ScaledWindow, area: PlotArea, thresholds: &[Threshold]) -> NcResult {
+    for t in thresholds {
+        let pair = level_color(t.level);
+        w.window.wattron(color_pair(pair))?;
+        match t.limit {
+            Limit::Above(v) | Limit::Below(v) => {
+                if let Some(row) = threshold_row(w, area, v) {
+                    for x in 0..area.cols {
+                        w.window.mvwprintw(YX(row, area.left + x), "╌")?;
+                    }
+                }
+            },
+            Limit::At(x) => {
+                let col = area.left + w.col_of(x).round() as i32;
+                if col >= area.left && col < area.left + area.cols {
+                    for y in area.top..=area.bottom() {
+                        w.window.mvwprintw(YX(y, col), "╎")?;
+                    }
+                }
+            },
+        }
+        w.window.wattroff(color_pair(pair))?;
+    }
+    Ok(0)
+}
+
+/// Labels at the right end of horizontal threshold lines, drawn over the samples
+pub fn draw_threshold_labels(w: &mut ScaledWindow, area: PlotArea, thresholds: &[Threshold]) -> NcResult {
+    for t in thresholds {
+        if let Limit::Above(v) | Limit::Below(v) = t.limit {
+            if let Some(row) = threshold_row(w, area, v) {
+                let label = t.label();
+                let x = area.left + area.cols - label.chars().count() as i32;
+                let pair = level_color(t.level);
+                w.window.wattron(color_pair(pair))?;
+                w.window.mvwprintw(YX(row, x.max(area.left)), &label)?;
+                w.window.wattroff(color_pair(pair))?;
+            }
+        }
+    }
+    Ok(0)
+}
+
+pub fn draw_raster(window: &mut Window, area: PlotArea, raster: &Raster, cpair: i16) -> NcResult {
+    window.wattron(color_pair(cpair))?;
+    for (x, y) in raster.bins() {
+        if let Some(yx) = area.cell(*x, *y) {
+            window.mvwprintw(yx, "•")?;
+        }
+    }
+    window.wattroff(color_pair(cpair))
+}
+
+/// Raster cells shaded by how many samples they hold, on a log scale as a few cells tend to
+/// take most of the samples
+pub fn draw_density(window: &mut Window, area: PlotArea, raster: &Raster, cpair: i16) -> NcResult {
+    let counts = raster.counts();
+    let most = counts.iter().flatten().copied().max().unwrap_or(0);
+    let top = (DENSITY.len() - 1) as f64;
+    window.wattron(color_pair(cpair))?;
+    for (x, column) in counts.iter().enumerate() {
+        for (y, count) in column.iter().enumerate() {
+            if *count == 0 { continue }
+            let level = if most > 1 { (*count as f64).ln() / (most as f64).ln() * top } else { 0. };
+            if let Some(yx) = area.cell(x, y) {
+                window.mvwprintw(yx, DENSITY[level.round() as usize])?;
+            }
+        }
+    }
+    window.wattroff(color_pair(cpair))
+}
+
+/// Series names along the top row, each in the color of its points
+pub fn draw_legend(window: &mut Window, area: PlotArea, entries: &[(&str, i16)]) -> NcResult {
+    let YX(_, width) = window.shape().size;
+    let mut x = area.left;
+    for (name, cpair) in entries {
+        let label = format!("• {}", name);
+        let len = label.chars().count() as i32;
+        if x + len >= width { break }
+        window.wattron(color_pair(*cpair))?;
+        window.mvwprintw(YX(0, x), &label)?;
+        window.wattroff(color_pair(*cpair))?;
+        x += len + 2;
+    }
+    Ok(0)
+}
+
+/// Legend entries for series drawn in the default color order
+fn legend<'a>(names: impl Iterator<Item=&'a str>) -> Vec<(&'a str, i16)> {
+    names.enumerate().map(|(i, name)| (name, series_color(i))).collect()
+}
+
+/// Axis lines plus tick labels, using the scales and axes already set on the window. Timed
+/// axes get wall-clock labels whose precision follows the tick spacing.
+pub fn draw_axes(w: &mut ScaledWindow, area: PlotArea, vmax: f64, hmax: f64) -> NcResult {
+    let YX(_, width) = w.window.shape().size;
+    let axis_row = area.top + area.rows;
+    let (vaxis, haxis) = (w.vaxis(), w.haxis());
+
+    let axis = color_pair(theme::pair(|t| t.axis));
+    w.window.wattron(axis)?;
+    w.window.mvwvline(YX(area.top, area.left - 1), '│', area.rows)?;
+    w.window.mvwhline(YX(axis_row, area.left - 1), '└', 1)?;
+    w.window.mvwhline(YX(axis_row, area.left), '─', area.cols)?;
+    w.window.wattroff(axis)?;
+
+    let vmin = vaxis.inverse(w.voffset());
+    let (step, ticks) = vaxis.ticks(vmin, vmax, (area.rows / 3).max(1) as usize);
+    for v in ticks {
+        let row = area.bottom() - w.row_of(v).round() as i32;
+        if row < area.top || row > area.bottom() { continue }
+        let label = truncate(&vaxis.label(v, step), (LABEL_WIDTH - 2) as usize);
+        let x = area.left - 2 - label.chars().count() as i32;
+        w.window.mvwprintw(YX(row, x.max(0)), &label)?;
+        w.window.wattron(axis)?;
+        w.window.mvwhline(YX(row, area.left - 1), '├', 1)?;
+        w.window.wattroff(axis)?;
+    }
+
+    let hmin = haxis.inverse(w.hoffset());
+    let per_label = if haxis == Axis::Time { 12 } else { 10 };
+    let (step, ticks) = haxis.ticks(hmin, hmax, (area.cols / per_label).max(1) as usize);
+    let mut free_from = 0;
+    for x in ticks {
+        let col = area.left + w.col_of(x).round() as i32;
+        if col < area.left || col >= area.left + area.cols { continue }
+        let label = haxis.label(x, step);
+        let len = label.chars().count() as i32;
+        let start = (col - len / 2).max(free_from).min(width - 1 - len);
+        if start < free_from { continue }
+        w.window.wattron(axis)?;
+        w.window.mvwhline(YX(axis_row, col), '┴', 1)?;
+        w.window.wattroff(axis)?;
+        w.window.mvwprintw(YX(axis_row + 1, start), &label)?;
+        free_from = start + len + 1;
+    }
+    Ok(0)
+}
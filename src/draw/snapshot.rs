@@ -0,0 +1,179 @@
+//! Snapshot tests of the drawing code. Each test draws data into an in-memory screen and
+//! compares what it shows, characters and color pairs, with a text file checked in under
+//! `tests/snapshots`. Run them with `UPDATE_SNAPSHOTS=1` to write the files from what gets
+//! drawn instead, then review the changes to the files.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::axis::Axis;
+use crate::stream::{Bins, Stream};
+use crate::threshold::Threshold;
+use crate::yrange::{RangePolicy, YRange, PADDING};
+
+use super::attrs::pair_number;
+use super::backend::memory::Memory;
+use super::backend::{self, Backend, Cell};
+use super::colors::series_color;
+use super::heatmap::{self, Colormap, Palette};
+use super::plot::{self, Orientation};
+use super::theme::{self, Theme};
+use super::windows::{Shape, Style, Window, YX};
+use super::{main_window, sparkline, stats};
+
+/// Characters standing for the color pairs of cells, from pair 0 on
+const PAIRS: &str = " 123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn pair_char(cell: &Cell) -> char {
+    PAIRS.chars().nth(pair_number(cell.attrs) as usize).unwrap_or('?')
+}
+
+/// The screen as a snapshot: its characters, then the color pair of each cell
+fn render(screen: &[Vec<Cell>]) -> String {
+    let grid = |f: &dyn Fn(&Cell) -> char| screen.iter()
+        .map(|row| row.iter().map(f).collect::<String>().trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("{}\n--- pairs\n{}\n", grid(&|c| c.ch), grid(&pair_char))
+}
+
+/// Lines that differ, numbered, the expected one with `-` and the one drawn with `+`
+fn diff(expected: &str, actual: &str) -> String {
+    let (expected, actual): (Vec<_>, Vec<_>) = (expected.lines().collect(), actual.lines().collect());
+    let mut out = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        let (e, a) = (expected.get(i).copied(), actual.get(i).copied());
+        if e != a {
+            out.push_str(&format!("{:4} -{}\n     +{}\n", i + 1, e.unwrap_or(""), a.unwrap_or("")));
+        }
+    }
+    out
+}
+
+/// Runs `draw` on a blank screen of `size` and checks what it left there against snapshot
+/// `name`, or writes the snapshot in update mode
+fn assert_snapshot(name: &str, size: YX, draw: impl FnOnce()) {
+    let screen = Memory::new(size);
+    backend::install(Box::new(screen.clone()));
+    theme::apply();
+    draw();
+    let actual = render(&screen.cells());
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(format!("{}.txt", name));
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("no snapshot {}, run with UPDATE_SNAPSHOTS=1 to write it", path.display()));
+    if expected != actual {
+        panic!("drawing differs from {}, run with UPDATE_SNAPSHOTS=1 if it should\n{}", path.display(), diff(&expected, &actual));
+    }
+}
+
+/// `n` samples of a sine wave of amplitude `a`, a period every 40 samples
+fn wave(n: usize, a: f32, phase: f32) -> Vec<f32> {
+    (0..n).map(|i| a * (i as f32 * std::f32::consts::PI / 20. + phase).sin()).collect()
+}
+
+fn auto() -> YRange {
+    YRange::new(RangePolicy::Auto(PADDING))
+}
+
+#[test]
+fn series() {
+    let sin: Stream<f32> = Stream::new(wave(120, 1., 0.).into_iter());
+    let cos: Stream<f32> = Stream::new(wave(120, 2., 1.57).into_iter());
+    assert_snapshot("series", YX(16, 64), || {
+        let mut w = main_window(0);
+        plot::plot_frames(&mut w, &[("sin", sin.frame(0, 120)), ("cos", cos.frame(0, 120))], Axis::Linear, &auto(), &[]).unwrap();
+    });
+}
+
+#[test]
+fn series_light() {
+    let sin: Stream<f32> = Stream::new(wave(120, 1., 0.).into_iter());
+    assert_snapshot("series_light", YX(12, 48), || {
+        theme::set(Theme::light());
+        let mut w = main_window(0);
+        plot::plot_frames(&mut w, &[("sin", sin.frame(0, 120))], Axis::Linear, &auto(), &[]).unwrap();
+        crate::draw::status_line("theme: light", false, None);
+    });
+}
+
+#[test]
+fn series_timed_with_thresholds() {
+    // A sample a second from 2024-01-01 12:00:00 UTC
+    let time: Vec<f64> = (0..300).map(|i| 1704110400. + i as f64).collect();
+    let load: Stream<f32> = Stream::with_time(wave(300, 1., 0.).into_iter(), time);
+    let thresholds = vec![Threshold::parse("load>0.6").unwrap(), Threshold::parse("load<-0.8,warn").unwrap()];
+    assert_snapshot("series_timed_with_thresholds", YX(16, 64), || {
+        let mut w = main_window(0);
+        plot::plot_frames(&mut w, &[("load", load.frame(0, 300))], Axis::Linear, &auto(), &thresholds).unwrap();
+    });
+}
+
+#[test]
+fn series_log() {
+    let growth: Stream<f32> = Stream::new((0..80).map(|i| 1.1f32.powi(i)));
+    assert_snapshot("series_log", YX(14, 50), || {
+        let mut w = main_window(0);
+        plot::plot_frames(&mut w, &[("growth", growth.frame(0, 80))], Axis::Log, &auto(), &[]).unwrap();
+    });
+}
+
+#[test]
+fn scatter() {
+    let x: Stream<f32> = Stream::new(wave(200, 1., 0.).into_iter());
+    let y: Stream<f32> = Stream::new(wave(200, 1., 1.).into_iter());
+    let names = vec![String::from("y vs x")];
+    assert_snapshot("scatter", YX(16, 40), || {
+        let mut w = main_window(0);
+        plot::plot_scatter(&mut w, &names, &[(x.frame(0, 200), y.frame(0, 200))], false).unwrap();
+    });
+}
+
+#[test]
+fn histogram() {
+    let s: Stream<f32> = Stream::new(wave(400, 1., 0.).into_iter());
+    assert_snapshot("histogram", YX(14, 50), || {
+        let mut w = main_window(0);
+        let hist = s.frame(0, 400).histogram(Bins::Count(8), 40);
+        plot::plot_histogram(&mut w, "s", &hist, Orientation::Vertical, series_color(0)).unwrap();
+    });
+}
+
+#[test]
+fn bars() {
+    let s: Stream<f32> = Stream::new((0..12).map(|i| (i * i) as f32));
+    assert_snapshot("bars", YX(12, 40), || {
+        let mut w = main_window(0);
+        plot::plot_bars(&mut w, "squares", &s.frame(0, 12), series_color(1), &auto()).unwrap();
+    });
+}
+
+#[test]
+fn heatmap() {
+    let s: Stream<f32> = Stream::new(wave(400, 1., 0.).into_iter().zip(wave(400, 0.2, 0.3)).map(|(a, b)| a + b));
+    assert_snapshot("heatmap", YX(14, 50), || {
+        let mut w = main_window(0);
+        let colormap = Colormap::init(Palette::Viridis);
+        heatmap::plot_distribution(&mut w, "s", &s.frame(0, 400), &colormap, &auto()).unwrap();
+    });
+}
+
+#[test]
+fn stats_and_sparkline() {
+    let a: Stream<f32> = Stream::new(wave(100, 1., 0.).into_iter());
+    let b: Stream<f32> = Stream::new((0..100).map(|i| i as f32));
+    assert_snapshot("stats_and_sparkline", YX(18, 40), || {
+        let mut panel = Window::new(Shape { pos: YX(0, 0), size: YX(16, stats::PANEL_WIDTH) }, None);
+        let entries = [("a", a.frame(0, 100).stats(), series_color(0)), ("b", b.frame(0, 100).stats(), series_color(1))];
+        stats::draw_stats(&mut panel, &entries).unwrap();
+        let mut below = Window::new(Shape { pos: YX(16, 0), size: YX(2, 40) }, None);
+        below.wborder(Style::blank()).unwrap();
+        sparkline::draw_sparkline(&mut below, YX(0, 0), &a.frame(0, 100), 40, series_color(0)).unwrap();
+    });
+}
@@ -0,0 +1,36 @@
+use super::attrs::color_pair;
+
+use crate::stream::Frame;
+
+use super::windows::{*};
+
+/// Sparkline levels, lowest to highest
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The frame squeezed into at most `width` block characters, one per raster column. Longer
+/// frames are bucketed, each character standing for the mean of its bucket. Needs no terminal,
+/// so it can feed shell prompts as well as windows.
+pub fn sparkline(frame: &Frame<f32>, width: usize) -> String {
+    let n = frame.data().len();
+    if n == 0 || width == 0 { return String::new() }
+
+    let range = frame.max() - frame.min();
+    // Eight levels from one row: the top of the range maps to 7/8 of the row
+    let vscale = if range > 0. { range * 8. / 7. } else { 1. };
+    let hscale = match frame.time_span() {
+        Some((t0, t1)) if t1 > t0 => ((t1 - t0) / (width - 1).max(1) as f64) as f32,
+        _ => (n as f32 / width as f32).max(1.),
+    };
+
+    frame.raster(vscale, hscale).bars().iter()
+        .take(width)
+        .map(|eighths| LEVELS[(*eighths).min(7)])
+        .collect()
+}
+
+/// Draws the sparkline of a frame at `yx`, taking at most `width` cells
+pub fn draw_sparkline(window: &mut Window, yx: YX, frame: &Frame<f32>, width: usize, cpair: i16) -> NcResult {
+    window.wattron(color_pair(cpair))?;
+    window.mvwprintw(yx, &sparkline(frame, width))?;
+    window.wattroff(color_pair(cpair))
+}
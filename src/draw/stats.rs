@@ -0,0 +1,54 @@
+use super::attrs::color_pair;
+
+use crate::axis;
+use crate::stream::Stats;
+
+use super::windows::{*};
+
+/// Columns taken by the stats panel, borders included
+pub const PANEL_WIDTH: i32 = 30;
+
+/// Rows per series: its name, five rows of two figures and a blank line
+const ENTRY_ROWS: i32 = 7;
+
+/// Value with decimals enough to tell apart figures of a series spanning `span`
+fn figure(v: f32, span: f32) -> String {
+    if v.is_nan() { return String::from("-") }
+    let step = if span > 0. { span as f64 / 1000. } else { v.abs().max(1.) as f64 / 1000. };
+    axis::format_value(v as f64, step)
+}
+
+/// Summary of each series, stacked from the top of the panel for as many as fit
+pub fn draw_stats(window: &mut Window, entries: &[(&str, Stats, i16)]) -> NcResult {
+    let YX(height, width) = window.shape().size;
+    let inner = (width - 3) as usize;
+    let half = (inner / 2) as i32;
+    window.mvwprintw(YX(0, 2), " stats ")?;
+
+    for (i, (name, stats, cpair)) in entries.iter().enumerate() {
+        let top = 1 + i as i32 * ENTRY_ROWS;
+        if top + ENTRY_ROWS - 1 > height - 1 { break }
+        window.wattron(color_pair(*cpair))?;
+        window.mvwprintw(YX(top, 2), &truncate(&name.to_string(), inner))?;
+        window.wattroff(color_pair(*cpair))?;
+
+        let span = stats.max - stats.min;
+        let rows = [
+            (("n", stats.count.to_string()), ("last", figure(stats.last, span))),
+            (("min", figure(stats.min, span)), ("max", figure(stats.max, span))),
+            (("mean", figure(stats.mean, span)), ("med", figure(stats.median, span))),
+            (("std", figure(stats.std_dev, span)), ("p90", figure(stats.p90, span))),
+            (("p95", figure(stats.p95, span)), ("p99", figure(stats.p99, span))),
+        ];
+        for (r, ((l1, v1), (l2, v2))) in rows.iter().enumerate() {
+            let y = top + 1 + r as i32;
+            let cell = |label: &str, value: &str| {
+                let text = format!("{:<4} {:>w$}", label, value, w = half as usize - 6);
+                truncate(&text, half as usize)
+            };
+            window.mvwprintw(YX(y, 2), &cell(l1, v1))?;
+            window.mvwprintw(YX(y, 2 + half), &cell(l2, v2))?;
+        }
+    }
+    Ok(0)
+}
@@ -0,0 +1,225 @@
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use super::attrs::color_pair;
+use super::backend;
+use super::colors::{self, Color, COLOR_BLACK, COLOR_WHITE};
+
+/// Colors of everything drawn on screen. Exports draw on white, in the series colors alone.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Theme {
+    pub name: String,
+    /// Behind everything, the terminal's own when `Color::Default`
+    pub background: Color,
+    /// Labels, figures and anything else without a color of its own
+    pub text: Color,
+    /// Axis lines and ticks
+    pub axis: Color,
+    /// Borders of panels, lines there to set things apart rather than to be read
+    pub grid: Color,
+    /// Messages on the status line and the command being typed
+    pub highlight: Color,
+    /// Colors series are drawn in, in turn
+    pub series: Vec<Color>,
+}
+
+impl Theme {
+
+    /// For dark terminals, and the one used unless told otherwise
+    pub fn dark() -> Theme {
+        Theme {
+            name: String::from("dark"),
+            background: Color::Standard(COLOR_BLACK),
+            text: Color::Standard(COLOR_WHITE),
+            axis: Color::Rgb(0xb2, 0xb2, 0xb2),
+            grid: Color::Rgb(0x80, 0x80, 0x80),
+            highlight: Color::Rgb(0x87, 0xd7, 0xff),
+            series: vec![
+                Color::Rgb(0x5f, 0xd7, 0x5f),
+                Color::Rgb(0x3f, 0xc8, 0xd8),
+                Color::Rgb(0xe8, 0xc5, 0x3a),
+                Color::Rgb(0xc6, 0x78, 0xdd),
+                Color::Rgb(0x5a, 0x8c, 0xf0),
+                Color::Rgb(0xf0, 0x55, 0x55),
+            ],
+        }
+    }
+
+    /// The built-in theme called `name`
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            _ => None,
+        }
+    }
+
+    /// Dark colors over white, for light terminals
+    pub fn light() -> Theme {
+        Theme {
+            name: String::from("light"),
+            background: Color::Standard(COLOR_WHITE + 8),
+            text: Color::Standard(COLOR_BLACK),
+            axis: Color::Rgb(0x50, 0x50, 0x50),
+            grid: Color::Rgb(0x70, 0x70, 0x70),
+            highlight: Color::Rgb(0x1f, 0x5f, 0xa8),
+            series: vec![
+                Color::Rgb(0x2c, 0xa0, 0x2c),
+                Color::Rgb(0x17, 0x9c, 0xb0),
+                Color::Rgb(0xb0, 0x80, 0x00),
+                Color::Rgb(0xb0, 0x3a, 0xb8),
+                Color::Rgb(0x1f, 0x5f, 0xc8),
+                Color::Rgb(0xd6, 0x27, 0x28),
+            ],
+        }
+    }
+
+}
+
+/// `#rrggbb`, one of the standard color names, maybe `bright-`, or `default`
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    let names = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+    if s == "default" { return Ok(Color::Default) }
+    if let Some(hex) = s.strip_prefix('#') {
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+            _ => Err(format!("bad color '{}', expected #rrggbb", s)),
+        }
+    }
+    let (bright, name) = match s.strip_prefix("bright-") {
+        Some(name) => (8, name),
+        None => (0, s),
+    };
+    names.iter().position(|n| *n == name)
+        .map(|i| Color::Standard(i as i16 + bright))
+        .ok_or(format!("unknown color '{}'", s))
+}
+
+/// Themes in the format of the config file: a line with the name in brackets, then lines
+/// giving its colors, as in `axis = #808080` or `series = red green #5a8cf0`. Colors left
+/// out are those of the built-in theme of the same name, or of the dark one.
+pub fn parse(text: &str) -> Result<Vec<Theme>, String> {
+    let mut themes: Vec<Theme> = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue }
+        let error = |e: String| format!("line {}: {}", n + 1, e);
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let name = name.trim();
+            if name.is_empty() { return Err(error(String::from("theme without a name"))) }
+            themes.push(Theme { name: name.to_string(), ..Theme::builtin(name).unwrap_or_else(Theme::dark) });
+            continue
+        }
+        let theme = themes.last_mut().ok_or_else(|| error(String::from("colors before any [theme]")))?;
+        let (key, value) = match line.find('=') {
+            Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+            None => return Err(error(format!("expected KEY = COLOR, got '{}'", line))),
+        };
+        let color = || parse_color(value).map_err(error);
+        match key {
+            "background" => theme.background = color()?,
+            "text" => theme.text = color()?,
+            "axis" => theme.axis = color()?,
+            "grid" => theme.grid = color()?,
+            "highlight" => theme.highlight = color()?,
+            "series" => {
+                let series = value.split_whitespace().map(parse_color).collect::<Result<Vec<_>, _>>().map_err(error)?;
+                if series.is_empty() { return Err(error(String::from("series needs a color at least"))) }
+                theme.series = series;
+            },
+            _ => return Err(error(format!("unknown key '{}'", key))),
+        }
+    }
+    Ok(themes)
+}
+
+/// Where user themes are read from, `grust/themes` in the config directory
+pub fn config_path() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("grust").join("themes"))
+}
+
+/// The built-in themes followed by the user's, who can redefine them by name
+pub fn load() -> Result<Vec<Theme>, String> {
+    let mut themes = vec![Theme::dark(), Theme::light()];
+    let path = match config_path() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(themes),
+    };
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    for theme in parse(&text).map_err(|e| format!("{}: {}", path.display(), e))? {
+        match themes.iter().position(|t| t.name == theme.name) {
+            Some(i) => themes[i] = theme,
+            None => themes.push(theme),
+        }
+    }
+    Ok(themes)
+}
+
+thread_local! {
+    static CURRENT: RefCell<Theme> = RefCell::new(Theme::dark());
+}
+
+/// Runs `f` on the theme drawing uses
+pub fn with<R>(f: impl FnOnce(&Theme) -> R) -> R {
+    CURRENT.with(|t| f(&t.borrow()))
+}
+
+/// Makes `theme` the one drawing uses, from the next frame on
+pub fn set(theme: Theme) {
+    CURRENT.with(|t| *t.borrow_mut() = theme);
+    apply();
+}
+
+/// Sets the installed backend up for the current theme: pairs given out afresh, and the
+/// background under everything drawn without a color of its own
+pub fn apply() {
+    colors::init();
+    let base = color_pair(pair(|t| t.text));
+    backend::with(|b| b.set_background(base));
+}
+
+/// Pair drawing the color `role` picks from the theme over its background
+pub fn pair(role: impl FnOnce(&Theme) -> Color) -> i16 {
+    colors::text_pair(with(role))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_from_config() {
+        let text = "\
+# solarized, more or less
+[solarized]
+background = #002b36
+text = bright-white
+series = #268bd2 cyan default
+
+[light]
+axis = black
+";
+        let themes = parse(text).unwrap();
+        assert_eq!(themes.len(), 2);
+        let solarized = &themes[0];
+        assert_eq!(solarized.name, "solarized");
+        assert_eq!(solarized.background, Color::Rgb(0x00, 0x2b, 0x36));
+        assert_eq!(solarized.text, Color::Standard(15));
+        assert_eq!(solarized.series, [Color::Rgb(0x26, 0x8b, 0xd2), Color::Standard(6), Color::Default]);
+        // Left out, as in the dark theme
+        assert_eq!(solarized.grid, Theme::dark().grid);
+        assert_eq!(themes[1].axis, Color::Standard(COLOR_BLACK));
+        assert_eq!(themes[1].background, Theme::light().background);
+
+        assert_eq!(parse("axis = red").unwrap_err(), "line 1: colors before any [theme]");
+        assert_eq!(parse("[a]\naxis = #12345").unwrap_err(), "line 2: bad color '#12345', expected #rrggbb");
+        assert_eq!(parse("[a]\nborder = red").unwrap_err(), "line 2: unknown key 'border'");
+        assert_eq!(parse("[a]\nseries = red pink").unwrap_err(), "line 2: unknown color 'pink'");
+    }
+}
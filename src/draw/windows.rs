@@ -5,10 +5,13 @@ use std::fmt::Debug;
 use std::collections::HashMap;
 use std::cell::RefCell;
 
-use ncurses::*;
 use unicode_segmentation::UnicodeSegmentation;
 
+use super::attrs::{color_pair, Attrs};
+use super::backend;
 use super::colors::{*};
+use super::theme;
+use crate::axis::Axis;
 
 pub fn truncate(string : &String, limit : usize) -> String {
     let title_str = &string[..];
@@ -24,13 +27,10 @@ pub fn truncate(string : &String, limit : usize) -> String {
 }
 
 pub fn screen_size() -> YX {
-    let mut max_y = 0;
-    let mut max_x = 0;
-    getmaxyx(stdscr(), &mut max_y, &mut max_x);
-    YX(max_y,max_x)
+    backend::with(|b| b.size())
 }
 
-#[derive(Copy,Clone,Debug)]
+#[derive(Copy,Clone,Debug,PartialEq)]
 pub struct YX(pub i32, pub i32);
 impl YX {
     pub fn from_u(y: usize, x: usize) -> YX {
@@ -68,27 +68,7 @@ pub struct Shape {
 
 #[derive(Debug)]
 pub enum WindowError {
-    Boxx,
-    WBorder,
-    WClear,
-    WRefresh,
-    WNoutRefresh,
-    RedrawWin,
-    WHline,
-    WVline,
-    MvWHline,
-    MvWVline,
-    MvHline,
-    MvVline,
-    WPrintw,
-    MvWPrintw,
-    WAttrOn,
-    WAttrOff,
-    WResize,
-    MvWin,
-    Touchwin,
     FindLayer,
-    DeleteSubWin,
 }
 
 #[derive(Debug)]
@@ -99,17 +79,17 @@ pub enum NcError {
 
 pub type NcResult = Result<i32, NcError>;
 
-fn __call_wrapper(code: i32, error: NcError) -> NcResult {
-    if code < 0 { Err(error) } else { Ok(code) }
-}
-
 pub fn nc_refresh() -> NcResult {
-    __call_wrapper(refresh(), NcError::Error)
+    backend::with(|b| b.flush());
+    Ok(0)
 }
 
+/// Rectangle of the screen drawn through the installed backend, in coordinates relative to
+/// its top left corner. Drawing outside of it is cut off.
 pub struct Window {
-    main: WINDOW,
     shape: Shape,
+    /// Attributes of what gets drawn, see `wattron`
+    attrs: Attrs,
     child_list: Vec<WindowId>,
     child_hash: HashMap<WindowId, Window>,
     id: WindowId,
@@ -117,26 +97,20 @@ pub struct Window {
 
 impl Window {
 
-    pub fn new(shape: Shape, style: Option<(chtype, chtype)>) -> Window {
-
-        let Shape { pos: YX(y, x), size: YX(lines, cols) } = shape;
+    /// Blank window, boxed with the given vertical and horizontal lines or plain ones
+    pub fn new(shape: Shape, style: Option<(char, char)>) -> Window {
 
         let mut w = Window {
-            main: newwin(lines, cols, y, x),
             shape: shape,
+            attrs: 0,
             child_list: Vec::new(),
             child_hash: HashMap::new(),
             id: WindowId::from("main"),
         };
 
-        if let Some((att1, att2)) = style {
-            w.box_(att1, att2);
-            // box_(w.main, att1, att2);
-        }
-        else {
-            w.box_(0, 0);
-            // box_(w.main, 0, 0);
-        }
+        let _ = w.wclear();
+        let (vertical, horizontal) = style.unwrap_or(('│', '─'));
+        let _ = w.box_(vertical, horizontal);
 
         w
 
@@ -146,12 +120,26 @@ impl Window {
         self.shape
     }
 
-    fn __call_wrapper(code: i32, error: WindowError) -> NcResult {
-        if code < 0 { Err(NcError::DrawError(error)) } else { Ok(code) }
+    /// Draws `s` at `yx` in the window attributes, keeping to the window
+    fn put(&mut self, yx: YX, s: &str) -> NcResult {
+        let YX(lines, cols) = self.shape.size;
+        let YX(y, x) = yx;
+        if y < 0 || y >= lines { return Ok(0) }
+        let skip = (-x).max(0) as usize;
+        let visible: String = s.chars().skip(skip).take((cols - x.max(0)).max(0) as usize).collect();
+        if visible.is_empty() { return Ok(0) }
+        let at = self.shape.pos + YX(y, x.max(0));
+        let attrs = self.attrs;
+        backend::with(|b| {
+            b.set_attrs(attrs);
+            b.print(at, &visible);
+            b.set_attrs(0);
+        });
+        Ok(0)
     }
 
-    pub fn box_(&mut self, att1: chtype, att2: chtype) -> NcResult {
-        Window::__call_wrapper(box_(self.main, att1, att2), WindowError::Boxx)
+    pub fn box_(&mut self, vertical: char, horizontal: char) -> NcResult {
+        self.wborder(Style::new(vertical, vertical, horizontal, horizontal, '┌', '┐', '└', '┘'))
     }
 
     pub fn wborder(&mut self, style: Style) -> NcResult {
@@ -163,118 +151,92 @@ impl Window {
             blc, brc,
         } = style;
 
-        let code = wborder(self.main,
-            ls, rs,
-            ts, bs,
-            tlc, trc,
-            blc, brc,
-        );
-        Window::__call_wrapper(code, WindowError::WBorder)
-    }
-
+        let YX(lines, cols) = self.shape.size;
+        if lines < 2 || cols < 2 { return Ok(0) }
+        // In the theme's grid color, whatever the window draws in
+        let attrs = self.attrs;
+        self.attrs = color_pair(theme::pair(|t| t.grid));
+        self.mvwhline(YX(0, 1), ts, cols - 2)?;
+        self.mvwhline(YX(lines - 1, 1), bs, cols - 2)?;
+        self.mvwvline(YX(1, 0), ls, lines - 2)?;
+        self.mvwvline(YX(1, cols - 1), rs, lines - 2)?;
+        self.put(YX(0, 0), &tlc.to_string())?;
+        self.put(YX(0, cols - 1), &trc.to_string())?;
+        self.put(YX(lines - 1, 0), &blc.to_string())?;
+        self.put(YX(lines - 1, cols - 1), &brc.to_string())?;
+        self.attrs = attrs;
+        Ok(0)
+    }
+
+    /// Blanks the whole window
     pub fn wclear(&mut self) -> NcResult {
-        Window::__call_wrapper(wclear(self.main), WindowError::WClear)
+        let YX(lines, cols) = self.shape.size;
+        let attrs = self.attrs;
+        self.attrs = 0;
+        let blank = " ".repeat(cols.max(0) as usize);
+        for y in 0..lines {
+            self.put(YX(y, 0), &blank)?;
+        }
+        self.attrs = attrs;
+        Ok(0)
     }
 
     pub fn wrefresh(&mut self) -> NcResult {
-        Window::__call_wrapper(wrefresh(self.main), WindowError::WRefresh)
+        nc_refresh()
     }
 
+    /// Everything is drawn on the one screen, there is nothing to stage
     pub fn wnoutrefresh(&mut self) -> NcResult {
-        Window::__call_wrapper(wnoutrefresh(self.main), WindowError::WNoutRefresh)
+        Ok(0)
     }
 
     pub fn redrawwin(&mut self) -> NcResult {
-        Window::__call_wrapper(redrawwin(self.main), WindowError::RedrawWin)
-    }
-
-    pub fn whline(&mut self, ch: chtype, n: i32) -> NcResult {
-        Window::__call_wrapper(whline(self.main, ch, n), WindowError::WHline)
-    }
-
-    pub fn wvline(&mut self, ch: chtype, n: i32) -> NcResult {
-        Window::__call_wrapper(wvline(self.main, ch, n), WindowError::WVline)
-    }
-
-    pub fn mvwhline(&mut self, yx: YX, ch: chtype, n: i32) -> NcResult {
-        let YX(y,x) = yx;
-        Window::__call_wrapper(mvwhline(self.main, y, x, ch, n), WindowError::MvWHline)
-    }
-
-    pub fn mvwvline(&mut self, yx: YX, ch: chtype, n: i32) -> NcResult {
-        let YX(y,x) = yx;
-        Window::__call_wrapper(mvwvline(self.main, y, x, ch, n), WindowError::MvWVline)
+        nc_refresh()
     }
 
-    pub fn mvhline(yx: YX, ch: chtype, n: i32) -> NcResult {
-        let YX(y,x) = yx;
-        Window::__call_wrapper(mvhline(y,x,ch,n), WindowError::MvHline)
+    pub fn mvwhline(&mut self, yx: YX, ch: char, n: i32) -> NcResult {
+        self.put(yx, &ch.to_string().repeat(n.max(0) as usize))
     }
 
-    pub fn mvvline(yx: YX, ch: chtype, n: i32) -> NcResult {
+    pub fn mvwvline(&mut self, yx: YX, ch: char, n: i32) -> NcResult {
         let YX(y,x) = yx;
-        Window::__call_wrapper(mvvline(y,x,ch,n), WindowError::MvVline)
-    }
-
-    pub fn wprintw(&mut self, s: &str) -> NcResult {
-        Window::__call_wrapper(wprintw(self.main, s), WindowError::WPrintw)
+        let ch = ch.to_string();
+        for i in 0..n {
+            self.put(YX(y + i, x), &ch)?;
+        }
+        Ok(0)
     }
 
     pub fn mvwprintw(&mut self, yx: YX, s: &str) -> NcResult {
-        let YX(y,x) = yx;
-        Window::__call_wrapper(mvwprintw(self.main, y, x, s), WindowError::MvWPrintw)
+        self.put(yx, s)
     }
 
-    // fn split_vline(&mut self, x: i32) {
-    //     let YX(h,w) = self.size();
-    //     let YX(y0,x0) = self.pos();
-    //     self.mvwvline(YX(0,x0+x), ACS_TTEE(), 1);
-    //     self.mvwvline(YX(1,x0+x), 0, h-2);
-    //     self.mvwvline(YX(h-1,x0+x), ACS_BTEE(), 1);
-    // }
-
-    // fn split_hline(&mut self, y: i32) {
-    //     let YX(h,w) = self.size();
-    //     let YX(y0,x0) = self.pos();
-    //     self.mvwhline(YX(y0+y,0), ACS_LTEE(), 1);
-    //     self.mvwhline(YX(y0+y,1), 0, w-2);
-    //     self.mvwhline(YX(y0+y, w-1), ACS_RTEE(), 1);
-    // }
-
-    pub fn wattron(&mut self, color_pair: NCURSES_ATTR_T) -> NcResult {
-        Window::__call_wrapper(wattron(self.main, color_pair), WindowError::WAttrOn)
+    pub fn wattron(&mut self, attrs: Attrs) -> NcResult {
+        self.attrs |= attrs;
+        Ok(0)
     }
 
-    pub fn wattroff(&mut self, color_pair: NCURSES_ATTR_T) -> NcResult {
-        Window::__call_wrapper(wattroff(self.main, color_pair), WindowError::WAttrOff)
+    pub fn wattroff(&mut self, attrs: Attrs) -> NcResult {
+        self.attrs &= !attrs;
+        Ok(0)
     }
 
-    // fn wattr_set(&mut self, attr: NCURSES_ATTR_T, cpair: NCURSES_ATTR_T) {
-    //     wattr_set(self.main, attr, cpair);
-    // }
-
     pub fn wresize(&mut self, lines: i32, cols: i32) -> NcResult {
-        let code = wresize(self.main, lines, cols);
-        if code > 0 {
-            self.shape.size = YX(lines, cols);
-        }
-        Window::__call_wrapper(code, WindowError::WResize)
+        self.shape.size = YX(lines, cols);
+        Ok(0)
     }
 
     pub fn mvwin(&mut self, y: i32, x: i32) -> NcResult {
-        let code = mvwin(self.main, y, x);
-        if code > 0 {
-            self.shape.pos = YX(y, x);
-        }
-        Window::__call_wrapper(code, WindowError::MvWin)
+        self.shape.pos = YX(y, x);
+        Ok(0)
     }
 
+    /// Window over part of this one, `shape` being in screen coordinates
     pub fn subwin(&mut self, shape: &Shape, id: &str) -> Option<Window> {
-        let Shape { pos: YX(y, x), size: YX(lines, cols) } = shape;
         let wid = WindowId::from(id);
         let sw = Window{
-            main: subwin(self.main, *lines, *cols, *y, *x),
             shape: shape.clone(),
+            attrs: 0,
             child_list: Vec::new(),
             child_hash: HashMap::new(),
             id: wid.clone(),
@@ -306,7 +268,7 @@ impl Window {
         }
 
     fn touchwin(&mut self) -> NcResult {
-        Window::__call_wrapper(touchwin(self.main), WindowError::Touchwin)
+        Ok(0)
     }
 
     fn find_layer(&self, id: &str) -> NcResult {
@@ -330,19 +292,11 @@ impl Window {
             drop(window)
         }
         self.child_list.remove(wid_position as usize);
-        // self.wrefresh()?;
-        // self.redrawwin()?;
         self.wrefresh()
     }
 
 }
 
-impl Drop for Window {
-    fn drop(&mut self) {
-        delwin(self.main);
-    }
-}
-
 
 #[derive(Clone,Debug,PartialEq,Eq,Hash)]
 pub struct WindowId (String);
@@ -356,15 +310,18 @@ impl WindowId {
 
 pub struct ScaledWindow {
     pub window: Window,
-    vscale: f32,
-    voffset: f32,
-    hscale: f32,
-    hoffset: f32,
+    vscale: f64,
+    voffset: f64,
+    hscale: f64,
+    hoffset: f64,
+    /// Scales and offsets are in the linear coordinate of each axis, see `Axis::forward`
+    vaxis: Axis,
+    haxis: Axis,
 }
 
-impl <'a> ScaledWindow {
+impl ScaledWindow {
 
-    pub fn new(shape: Shape, style: Option<(chtype, chtype)>) -> ScaledWindow {
+    pub fn new(shape: Shape, style: Option<(char, char)>) -> ScaledWindow {
 
         ScaledWindow {
             window: Window::new(shape, style),
@@ -372,214 +329,112 @@ impl <'a> ScaledWindow {
             voffset: 0.,
             hscale: 1.,
             hoffset: 0.,
+            vaxis: Axis::Linear,
+            haxis: Axis::Linear,
         }
     }
 
-    pub fn set_vscale(&mut self, scale: f32) {
+    pub fn set_vscale(&mut self, scale: f64) {
         self.vscale = scale;
     }
 
-    pub fn set_hscale(&mut self, scale: f32) {
+    pub fn set_hscale(&mut self, scale: f64) {
         self.hscale = scale;
     }
 
 
-    pub fn set_voffset(&mut self, offset: f32) {
+    pub fn set_voffset(&mut self, offset: f64) {
         self.voffset = offset;
     }
 
 
-    pub fn set_hoffset(&mut self, offset: f32) {
+    pub fn set_hoffset(&mut self, offset: f64) {
         self.hoffset = offset;
     }
 
-    pub fn set_scale_offset(&mut self, vscale: f32, voffset: f32, hscale: f32, hoffset: f32) {
+    pub fn set_scale_offset(&mut self, vscale: f64, voffset: f64, hscale: f64, hoffset: f64) {
         self.set_vscale(vscale);
         self.set_voffset(voffset);
         self.set_hscale(hscale);
         self.set_hoffset(hoffset);
     }
 
-}
-
-
-
-
-pub trait NcursesWindow {
-    fn window(&mut self) -> WINDOW;
-    fn pos(&self) -> YX;
-    fn size(&self) -> YX;
-
-    fn box_(&mut self, att1: chtype, att2: chtype) {
-        box_(self.window(), att1, att2);
-    }
-
-    fn wborder(&mut self, style: Style) {
-
-        let Style {
-            ls, rs,
-            ts, bs,
-            tlc, trc,
-            blc, brc,
-        } = style;
-
-        wborder(self.window(),
-            ls, rs,
-            ts, bs,
-            tlc, trc,
-            blc, brc,
-        );
-    }
-
-    fn wclear(&mut self) {
-        wclear(self.window());
-    }
-
-    fn wrefresh(&mut self) {
-        wrefresh(self.window());
-    }
-
-    fn wnoutrefresh(&mut self) {
-        wnoutrefresh(self.window());
-    }
-
-    fn redrawwin(&mut self) {
-        redrawwin(self.window());
-    }
-
-    fn whline(&mut self, ch: chtype, n: i32) {
-        whline(self.window(), ch, n);
-    }
-
-    fn wvline(&mut self, ch: chtype, n: i32) {
-        wvline(self.window(), ch, n);
-        // wrefresh(self.window());
-    }
-
-    fn mvwhline(&mut self, yx: YX, ch: chtype, n: i32) {
-        let YX(y,x) = yx;
-        mvwhline(self.window(), y,x,ch,n);
-        // wrefresh(self.window());
-    }
-
-    fn mvwvline(&mut self, yx: YX, ch: chtype, n: i32) {
-        let YX(y,x) = yx;
-        mvwvline(self.window(), y, x, ch, n);
-        // wrefresh(self.window());
-    }
-
-    fn mvhline(yx: YX, ch: chtype, n: i32) {
-        let YX(y,x) = yx;
-        mvhline(y,x,ch,n);
+    pub fn vscale(&self) -> f64 {
+        self.vscale
     }
 
-    fn mvvline(yx: YX, ch: chtype, n: i32) {
-        let YX(y,x) = yx;
-        mvvline(y,x,ch,n);
+    pub fn hscale(&self) -> f64 {
+        self.hscale
     }
 
-    fn wprintw(&mut self, s: &str) -> i32 {
-        wprintw(self.window(), s)
+    pub fn voffset(&self) -> f64 {
+        self.voffset
     }
 
-    fn mvwprintw(&mut self, yx: YX, s: &str) -> i32 {
-        let YX(y,x) = yx;
-        mvwprintw(self.window(), y, x, s)
+    /// The horizontal offset is a timestamp for timed plots, hence the f64 scales
+    pub fn hoffset(&self) -> f64 {
+        self.hoffset
     }
 
-    fn split_vline(&mut self, x: i32) {
-        let YX(h,w) = self.size();
-        let YX(y0,x0) = self.pos();
-        self.mvwvline(YX(0,x0+x), ACS_TTEE(), 1);
-        self.mvwvline(YX(1,x0+x), 0, h-2);
-        self.mvwvline(YX(h-1,x0+x), ACS_BTEE(), 1);
+    pub fn set_axes(&mut self, haxis: Axis, vaxis: Axis) {
+        self.haxis = haxis;
+        self.vaxis = vaxis;
     }
 
-    fn split_hline(&mut self, y: i32) {
-        let YX(h,w) = self.size();
-        let YX(y0,x0) = self.pos();
-        self.mvwhline(YX(y0+y,0), ACS_LTEE(), 1);
-        self.mvwhline(YX(y0+y,1), 0, w-2);
-        self.mvwhline(YX(y0+y, w-1), ACS_RTEE(), 1);
+    pub fn vaxis(&self) -> Axis {
+        self.vaxis
     }
 
-    fn wattron(&mut self, color_pair: NCURSES_ATTR_T) {
-        wattron(self.window(), color_pair);
+    pub fn haxis(&self) -> Axis {
+        self.haxis
     }
 
-    fn wattroff(&mut self, color_pair: NCURSES_ATTR_T) {
-        wattroff(self.window(), color_pair);
+    /// Rows above the bottom of the plot for a value, fractional
+    pub fn row_of(&self, v: f64) -> f64 {
+        (self.vaxis.forward(v) - self.voffset) / self.vscale
     }
 
-    // fn wattr_set(&mut self, attr: NCURSES_ATTR_T, cpair: NCURSES_ATTR_T) {
-    //     wattr_set(self.window(), attr, cpair);
-    // }
-
-    fn wresize(&mut self, lines: i32, cols: i32) {
-        wresize(self.window(), lines, cols);
+    /// Columns from the left of the plot for an x coordinate (index or timestamp), fractional
+    pub fn col_of(&self, x: f64) -> f64 {
+        (self.haxis.forward(x) - self.hoffset) / self.hscale
     }
 
-    fn mvwin(&mut self, lines: i32, cols: i32) {
-        mvwin(self.window(), lines, cols);
-    }
+}
 
 
-    fn touchwin(&mut self) {
-        touchwin(self.window());
-    }
 
-}
 
+/// Characters of a window border: sides, then corners
 pub struct Style {
-     ls: chtype,
-     rs: chtype,
-     ts: chtype,
-     bs: chtype,
-    tlc: chtype,
-    trc: chtype,
-    blc: chtype,
-    brc: chtype,
+     ls: char,
+     rs: char,
+     ts: char,
+     bs: char,
+    tlc: char,
+    trc: char,
+    blc: char,
+    brc: char,
 }
 
 impl Style {
 
     pub fn new(ls: char, rs: char, ts: char, bs: char, tlc: char, trc: char, blc: char, brc: char,) -> Self {
         Self {
-            ls: ls as chtype,
-            rs: rs as chtype,
-            ts: ts as chtype,
-            bs: bs as chtype,
-            tlc: tlc as chtype,
-            trc: trc as chtype,
-            blc: blc as chtype,
-            brc: brc as chtype,
+            ls, rs,
+            ts, bs,
+            tlc, trc,
+            blc, brc,
         }
     }
 
-    pub fn from(ls: chtype, rs: chtype, ts: chtype, bs: chtype, tlc: chtype, trc: chtype, blc: chtype, brc: chtype,) -> Self {
-        Self {
-            ls: ls as chtype,
-            rs: rs as chtype,
-            ts: ts as chtype,
-            bs: bs as chtype,
-            tlc: tlc as chtype,
-            trc: trc as chtype,
-            blc: blc as chtype,
-            brc: brc as chtype,
-        }
+    /// A border of blanks
+    pub fn blank() -> Self {
+        Self::new(' ', ' ', ' ', ' ', ' ', ' ', ' ', ' ')
     }
 
+    /// Plain box lines
     pub fn default() -> Self {
-        Self {
-            ls: 0,
-            rs: 0,
-            ts: 0,
-            bs: 0,
-            tlc: 0,
-            trc: 0,
-            blc: 0,
-            brc: 0,
-        }
+        Self::new('│', '│', '─', '─', '┌', '┐', '└', '┘')
     }
 
 }
@@ -0,0 +1,75 @@
+use crate::draw::attrs::{pair_number, A_BOLD, A_REVERSE, A_UNDERLINE};
+use crate::draw::backend::Cell;
+use crate::draw::colors::{color_rgb, pair_colors};
+use crate::draw::theme;
+
+/// Escape sequence selecting `color` for the text, or for the background
+fn sgr_color(color: i16, background: bool) -> String {
+    let base = if background { 40 } else { 30 };
+    match color {
+        c if c < 8 => (base + c).to_string(),
+        c if c < 16 => (base + 60 + c - 8).to_string(),
+        c => match color_rgb(c) {
+            Some((r, g, b)) => format!("{};2;{};{};{}", base + 8, r, g, b),
+            None => format!("{};5;{}", base + 8, c),
+        },
+    }
+}
+
+/// Text and background colors of the pair the cell was drawn in, -1 for those left to the
+/// terminal: the theme's text and background, so the file reads on any
+fn colors(cell: &Cell, base: i16) -> (i16, i16) {
+    let (fg, bg) = match pair_number(cell.attrs) {
+        p if p == base => (-1, -1),
+        p => pair_colors(p),
+    };
+    let (_, background) = pair_colors(base);
+    (fg, if bg == background { -1 } else { bg })
+}
+
+/// Parameters of the escape sequence drawing a cell, empty for the terminal's defaults
+fn style(cell: &Cell, base: i16) -> String {
+    let (fg, bg) = colors(cell, base);
+    let mut params = Vec::new();
+    if cell.attrs & A_BOLD != 0 { params.push(String::from("1")) }
+    if cell.attrs & A_UNDERLINE != 0 { params.push(String::from("4")) }
+    if cell.attrs & A_REVERSE != 0 { params.push(String::from("7")) }
+    if fg >= 0 { params.push(sgr_color(fg, false)) }
+    if bg >= 0 { params.push(sgr_color(bg, true)) }
+    params.join(";")
+}
+
+/// Whether the cell shows nothing, spaces with a background of their own only count in color
+fn blank(cell: &Cell, color: bool, base: i16) -> bool {
+    let (_, bg) = colors(cell, base);
+    let background = bg >= 0 || cell.attrs & A_REVERSE != 0;
+    cell.ch == ' ' && !(color && background)
+}
+
+/// The screen as text, with escape sequences for the colors when `color` is set so it shows
+/// as it did when printed to a terminal. Trailing blanks are left out.
+pub fn render(screen: &[Vec<Cell>], color: bool) -> String {
+    let base = theme::pair(|t| t.text);
+    let mut lines: Vec<String> = screen.iter().map(|row| {
+        let shown = row.iter().rposition(|c| !blank(c, color, base)).map_or(0, |i| i + 1);
+        let mut line = String::new();
+        let mut current = String::new();
+        for cell in &row[..shown] {
+            if color {
+                let wanted = style(cell, base);
+                if wanted != current {
+                    line.push_str("\x1b[0m");
+                    if !wanted.is_empty() { line.push_str(&format!("\x1b[{}m", wanted)) }
+                    current = wanted;
+                }
+            }
+            line.push(cell.ch);
+        }
+        if !current.is_empty() { line.push_str("\x1b[0m") }
+        line
+    }).collect();
+    while lines.last().is_some_and(|l| l.is_empty()) { lines.pop(); }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
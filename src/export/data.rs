@@ -0,0 +1,99 @@
+use std::fmt::Write;
+
+use crate::interact::AppState;
+
+/// The samples on screen, one row per sample index: its timestamp, or the index itself for
+/// untimed data, then the value of every series, derived ones included, where it has one
+struct Table {
+    timed: bool,
+    names: Vec<String>,
+    rows: Vec<(f64, Vec<Option<f32>>)>,
+}
+
+impl Table {
+
+    fn of(app: &AppState) -> Result<Table, String> {
+        let frames = app.named_frames();
+        if frames.is_empty() { return Err(String::from("no samples to export")) }
+        let timed = frames.iter().any(|(_, f)| f.time().is_some());
+        let ini = frames.iter().map(|(_, f)| f.range().0).min().unwrap_or(0);
+        let end = frames.iter().map(|(_, f)| f.range().1).max().unwrap_or(0);
+
+        let mut rows = Vec::new();
+        for i in ini..end {
+            // Every series sharing the index has the same timestamp for it
+            let mut key = if timed { None } else { Some(i as f64) };
+            let values = frames.iter().map(|(_, frame)| {
+                let (ini, end) = frame.range();
+                if i < ini || i >= end { return None }
+                if let Some(time) = frame.time() { key = key.or(Some(time[i - ini])) }
+                Some(frame.data()[i - ini])
+            }).collect();
+            if let Some(key) = key { rows.push((key, values)) }
+        }
+        let names = frames.iter().map(|(name, _)| name.to_string()).collect();
+        Ok(Table { timed, names, rows })
+    }
+
+    fn key(&self) -> &'static str {
+        if self.timed { "time" } else { "index" }
+    }
+
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) { format!("\"{}\"", s.replace('"', "\"\"")) } else { s.to_string() }
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Header with the time or index column and the series names, then a line per sample.
+/// Series without a sample at some index, or with NaN there, leave its field empty.
+pub fn csv(app: &AppState) -> Result<String, String> {
+    let table = Table::of(app)?;
+    let mut out = String::from(table.key());
+    for name in &table.names {
+        out.push(',');
+        out.push_str(&csv_field(name));
+    }
+    out.push('\n');
+    for (key, values) in &table.rows {
+        let _ = write!(out, "{}", key);
+        for v in values {
+            out.push(',');
+            if let Some(v) = v.filter(|v| v.is_finite()) { let _ = write!(out, "{}", v); }
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// One JSON object per sample, as in `{"time":1760000000,"latency":12.5}`. Missing samples
+/// and values JSON can't hold, like NaN, are null.
+pub fn jsonl(app: &AppState) -> Result<String, String> {
+    let table = Table::of(app)?;
+    let mut out = String::new();
+    for (key, values) in &table.rows {
+        let _ = write!(out, "{{\"{}\":{}", table.key(), key);
+        for (name, v) in table.names.iter().zip(values) {
+            let _ = match v {
+                Some(v) if v.is_finite() => write!(out, ",{}:{}", json_string(name), v),
+                _ => write!(out, ",{}:null", json_string(name)),
+            };
+        }
+        out.push_str("}\n");
+    }
+    Ok(out)
+}
@@ -0,0 +1,310 @@
+use std::fmt::Write;
+
+use super::data::json_string;
+use super::{threshold_rgb, Mark, Scene};
+use crate::threshold::Limit;
+
+/// Page around the plot. The scene goes in place of `/*SCENE*/` and everything, the script
+/// included, is inline so the file works offline.
+const PAGE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>/*TITLE*/</title>
+<style>
+body { font-family: sans-serif; margin: 16px; color: #222; }
+h1 { font-size: 16px; margin: 0 0 6px; }
+#legend span { margin-right: 14px; font-size: 13px; }
+#legend i { display: inline-block; width: 9px; height: 9px; border-radius: 50%; margin-right: 4px; }
+#plot { display: block; border: 1px solid #ddd; cursor: crosshair; }
+#tip { position: absolute; display: none; pointer-events: none; background: rgba(255,255,255,0.92);
+       border: 1px solid #bbb; padding: 4px 6px; font-size: 12px; white-space: pre; }
+#help { font-size: 12px; color: #777; margin-top: 6px; }
+</style>
+</head>
+<body>
+<h1>/*TITLE*/</h1>
+<div id="legend"></div>
+<canvas id="plot" style="width: /*WIDTH*/px; height: /*HEIGHT*/px"></canvas>
+<div id="tip"></div>
+<div id="help">Drag to zoom into a box, scroll to zoom in time, double click to reset.</div>
+<script>
+"use strict";
+const scene = /*SCENE*/;
+const forward = {
+  linear: v => v, time: v => v, log: v => Math.log10(v),
+  symlog: v => Math.sign(v) * Math.log10(1 + Math.abs(v)),
+};
+const inverse = {
+  linear: u => u, time: u => u, log: u => Math.pow(10, u),
+  symlog: u => Math.sign(u) * (Math.pow(10, Math.abs(u)) - 1),
+};
+const margin = { top: 12, right: 24, bottom: 36, left: 72 };
+const canvas = document.getElementById("plot");
+const ctx = canvas.getContext("2d");
+const tip = document.getElementById("tip");
+const fx = forward[scene.haxis], fy = forward[scene.vaxis];
+// Shown ranges in the coordinates where the axes are linear
+let view, drag = null, hover = null;
+function reset() {
+  view = { x: [fx(scene.x[0]), fx(scene.x[1])], y: [fy(scene.y[0]), fy(scene.y[1])] };
+}
+
+const width = canvas.clientWidth, height = canvas.clientHeight;
+const ratio = window.devicePixelRatio || 1;
+canvas.width = width * ratio;
+canvas.height = height * ratio;
+ctx.scale(ratio, ratio);
+const plotW = width - margin.left - margin.right, plotH = height - margin.top - margin.bottom;
+const px = x => margin.left + (fx(x) - view.x[0]) / (view.x[1] - view.x[0]) * plotW;
+const py = y => margin.top + plotH - (fy(y) - view.y[0]) / (view.y[1] - view.y[0]) * plotH;
+const unpx = p => view.x[0] + (p - margin.left) / plotW * (view.x[1] - view.x[0]);
+const unpy = p => view.y[0] + (margin.top + plotH - p) / plotH * (view.y[1] - view.y[0]);
+
+function niceStep(span, n) {
+  const raw = span / Math.max(n, 1), mag = Math.pow(10, Math.floor(Math.log10(raw)));
+  return [1, 2, 5, 10].map(m => m * mag).find(s => s >= raw);
+}
+const TIME_STEPS = [0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5,
+                    1, 2, 5, 10, 15, 30, 60, 120, 300, 600, 900, 1800, 3600, 7200, 10800, 21600,
+                    43200, 86400, 172800, 604800, 2592000, 31536000];
+// Tick values inside [lo, hi], given in linear coordinates, and the step between them
+function ticks(axis, lo, hi, n) {
+  if (axis === "log" || axis === "symlog") {
+    // Powers of ten, and zero on symlog, thinned out to about n
+    const every = Math.max(1, Math.ceil((hi - lo) / n));
+    const top = Math.ceil(Math.max(Math.abs(lo), Math.abs(hi)));
+    const marks = axis === "symlog" ? [[0, 0]] : [];
+    for (let e = -top; e <= top; e += 1) {
+      if (axis === "log") marks.push([e, Math.pow(10, e)]);
+      else if (e >= 0) marks.push([e, Math.pow(10, e)], [e, -Math.pow(10, e)]);
+    }
+    const shown = marks.filter(([e, v]) => e % every === 0 && forward[axis](v) >= lo && forward[axis](v) <= hi);
+    return [0, shown.map(m => m[1]).sort((a, b) => a - b)];
+  }
+  const step = axis === "time"
+    ? (TIME_STEPS.find(s => s >= (hi - lo) / n) || niceStep(hi - lo, n))
+    : niceStep(hi - lo, n);
+  const out = [];
+  for (let v = Math.ceil(lo / step) * step; v <= hi + step * 1e-9; v += step) out.push(v);
+  return [step, out];
+}
+const pad = n => String(n).padStart(2, "0");
+function label(axis, v, step) {
+  if (axis === "time") {
+    // In UTC, as grust labels its own axes
+    const d = new Date(Math.round(v * 1000));
+    if (step >= 86400) return pad(d.getUTCMonth() + 1) + "-" + pad(d.getUTCDate());
+    const hm = pad(d.getUTCHours()) + ":" + pad(d.getUTCMinutes());
+    const ss = pad(d.getUTCSeconds());
+    if (step >= 60) return hm;
+    if (step >= 1) return hm + ":" + ss;
+    return pad(d.getUTCMinutes()) + ":" + ss + "." + String(d.getUTCMilliseconds()).padStart(3, "0");
+  }
+  if (Math.abs(v) < Math.abs(step) * 1e-9) v = 0;
+  if (v !== 0 && (Math.abs(v) >= 1e6 || Math.abs(v) < 1e-3)) return v.toExponential(1);
+  const decimals = step < 1 && step > 0 ? -Math.floor(Math.log10(step)) : 0;
+  return v.toFixed(decimals);
+}
+const value = v => String(Number(v.toPrecision(6)));
+const xlabel = x => scene.haxis === "time" ? label("time", x, 1) : value(x);
+
+function draw() {
+  ctx.clearRect(0, 0, width, height);
+  ctx.font = "12px sans-serif";
+  ctx.lineWidth = 1;
+  const [ystep, yticks] = ticks(scene.vaxis, view.y[0], view.y[1], Math.max(2, plotH / 50));
+  ctx.textAlign = "right";
+  for (const v of yticks) {
+    const y = Math.round(py(v)) + 0.5;
+    ctx.strokeStyle = "#e4e4e4";
+    ctx.beginPath(); ctx.moveTo(margin.left, y); ctx.lineTo(margin.left + plotW, y); ctx.stroke();
+    ctx.fillStyle = "#222";
+    ctx.fillText(label(scene.vaxis, v, ystep || Math.abs(v)), margin.left - 8, y + 4);
+  }
+  const [xstep, xticks] = ticks(scene.haxis, view.x[0], view.x[1], Math.max(2, plotW / 100));
+  ctx.textAlign = "center";
+  for (const v of xticks) {
+    const x = Math.round(px(v)) + 0.5;
+    ctx.strokeStyle = "#e4e4e4";
+    ctx.beginPath(); ctx.moveTo(x, margin.top); ctx.lineTo(x, margin.top + plotH); ctx.stroke();
+    ctx.fillStyle = "#222";
+    ctx.fillText(label(scene.haxis, v, xstep || Math.abs(v)), x, margin.top + plotH + 20);
+  }
+  ctx.strokeStyle = "#444";
+  ctx.beginPath();
+  ctx.moveTo(margin.left + 0.5, margin.top);
+  ctx.lineTo(margin.left + 0.5, margin.top + plotH + 0.5);
+  ctx.lineTo(margin.left + plotW, margin.top + plotH + 0.5);
+  ctx.stroke();
+
+  ctx.save();
+  ctx.beginPath(); ctx.rect(margin.left, margin.top, plotW, plotH); ctx.clip();
+  ctx.setLineDash([6, 4]);
+  for (const t of scene.thresholds) {
+    ctx.strokeStyle = t.color;
+    ctx.beginPath();
+    if (t.value !== undefined) { ctx.moveTo(margin.left, py(t.value)); ctx.lineTo(margin.left + plotW, py(t.value)); }
+    else { ctx.moveTo(px(t.at), margin.top); ctx.lineTo(px(t.at), margin.top + plotH); }
+    ctx.stroke();
+  }
+  ctx.setLineDash([]);
+  for (const s of scene.series) {
+    ctx.strokeStyle = ctx.fillStyle = s.color;
+    if (s.mark === "line") {
+      ctx.lineWidth = 1.5;
+      ctx.beginPath();
+      let down = false;
+      for (const [x, y] of s.points) {
+        const X = px(x), Y = y === null ? NaN : py(y);
+        if (!isFinite(X) || !isFinite(Y)) { down = false; continue; }
+        if (down) ctx.lineTo(X, Y); else ctx.moveTo(X, Y);
+        down = true;
+      }
+      ctx.stroke();
+    }
+    else {
+      for (const [x, y] of s.points) {
+        if (y === null) continue;
+        ctx.beginPath(); ctx.arc(px(x), py(y), 2, 0, 2 * Math.PI); ctx.fill();
+      }
+    }
+  }
+  if (hover) {
+    for (const h of hover) {
+      ctx.fillStyle = h.color;
+      ctx.beginPath(); ctx.arc(px(h.x), py(h.y), 4, 0, 2 * Math.PI); ctx.fill();
+    }
+  }
+  ctx.restore();
+  if (drag && drag.moved) {
+    ctx.fillStyle = "rgba(60, 120, 200, 0.15)";
+    ctx.fillRect(drag.x0, drag.y0, drag.x1 - drag.x0, drag.y1 - drag.y0);
+  }
+}
+
+// Samples under the pointer: the closest in time of each line, or the closest point overall
+function nearest(mx, my) {
+  const found = [];
+  for (const s of scene.series) {
+    let best = null, bestD = Infinity;
+    for (const [x, y] of s.points) {
+      if (y === null) continue;
+      const dx = px(x) - mx, dy = py(y) - my;
+      const d = s.mark === "line" ? Math.abs(dx) : Math.hypot(dx, dy);
+      if (d < bestD) { bestD = d; best = { name: s.name, color: s.color, x, y }; }
+    }
+    if (best && bestD < 20) found.push(best);
+  }
+  return found.length ? found : null;
+}
+
+function pointer(e) {
+  const r = canvas.getBoundingClientRect();
+  return [e.clientX - r.left, e.clientY - r.top];
+}
+canvas.addEventListener("mousedown", e => {
+  const [x, y] = pointer(e);
+  drag = { x0: x, y0: y, x1: x, y1: y, moved: false };
+});
+canvas.addEventListener("mousemove", e => {
+  const [x, y] = pointer(e);
+  if (drag) {
+    drag.x1 = x; drag.y1 = y;
+    drag.moved = Math.abs(drag.x1 - drag.x0) > 4 || Math.abs(drag.y1 - drag.y0) > 4;
+    hover = null; tip.style.display = "none";
+    draw();
+    return;
+  }
+  hover = nearest(x, y);
+  if (hover) {
+    tip.textContent = xlabel(hover[0].x) + "\n" + hover.map(h => h.name + ": " + value(h.y)).join("\n");
+    tip.style.left = (e.pageX + 14) + "px";
+    tip.style.top = (e.pageY + 14) + "px";
+    tip.style.display = "block";
+  }
+  else tip.style.display = "none";
+  draw();
+});
+window.addEventListener("mouseup", () => {
+  if (drag && drag.moved) {
+    const xs = [unpx(drag.x0), unpx(drag.x1)].sort((a, b) => a - b);
+    const ys = [unpy(drag.y0), unpy(drag.y1)].sort((a, b) => a - b);
+    if (xs[1] > xs[0] && ys[1] > ys[0]) view = { x: xs, y: ys };
+  }
+  drag = null;
+  draw();
+});
+canvas.addEventListener("mouseleave", () => { hover = null; tip.style.display = "none"; draw(); });
+canvas.addEventListener("wheel", e => {
+  e.preventDefault();
+  const at = unpx(pointer(e)[0]), k = e.deltaY > 0 ? 1.25 : 0.8;
+  view.x = [at - (at - view.x[0]) * k, at + (view.x[1] - at) * k];
+  draw();
+}, { passive: false });
+canvas.addEventListener("dblclick", () => { reset(); draw(); });
+
+const legend = document.getElementById("legend");
+for (const s of scene.series) {
+  const item = document.createElement("span");
+  const dot = document.createElement("i");
+  dot.style.background = s.color;
+  item.appendChild(dot);
+  item.appendChild(document.createTextNode(s.name));
+  legend.appendChild(item);
+}
+reset();
+draw();
+</script>
+</body>
+</html>
+"##;
+
+fn color((r, g, b): (u8, u8, u8)) -> String {
+    format!("\"#{:02x}{:02x}{:02x}\"", r, g, b)
+}
+
+fn number(v: f64) -> String {
+    if v.is_finite() { v.to_string() } else { String::from("null") }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// The scene as JSON for the page script
+fn scene_json(scene: &Scene) -> String {
+    let sc = &scene.scales;
+    let mut json = String::new();
+    let _ = write!(json, "{{\"haxis\":\"{}\",\"vaxis\":\"{}\",\"x\":[{},{}],\"y\":[{},{}],\"series\":[",
+                   sc.haxis.name(), sc.vaxis.name(), number(sc.x.0), number(sc.x.1), number(sc.y.0), number(sc.y.1));
+    for (i, series) in scene.series.iter().enumerate() {
+        let mark = match series.mark { Mark::Line => "line", Mark::Points => "points" };
+        let _ = write!(json, "{}{{\"name\":{},\"color\":{},\"mark\":\"{}\",\"points\":[",
+                       if i > 0 { "," } else { "" }, json_string(&series.name), color(series.rgb), mark);
+        for (j, (x, y)) in series.points.iter().filter(|(x, _)| x.is_finite()).enumerate() {
+            let _ = write!(json, "{}[{},{}]", if j > 0 { "," } else { "" }, x, number(*y));
+        }
+        json.push_str("]}");
+    }
+    json.push_str("],\"thresholds\":[");
+    for (i, t) in scene.thresholds.iter().enumerate() {
+        let place = match t.limit {
+            Limit::Above(v) | Limit::Below(v) => format!("\"value\":{}", number(v)),
+            Limit::At(x) => format!("\"at\":{}", number(x)),
+        };
+        let _ = write!(json, "{}{{{},\"color\":{}}}", if i > 0 { "," } else { "" }, place, color(threshold_rgb(t)));
+    }
+    json.push_str("]}");
+    // Names can't end the script early
+    json.replace("</", "<\\/")
+}
+
+/// Standalone page with the scene drawn on a `width` by `height` canvas. Hovering shows the
+/// values under the pointer, dragging zooms into a box and scrolling zooms in time.
+pub fn render(scene: &Scene, (width, height): (u32, u32)) -> String {
+    PAGE.replace("/*TITLE*/", &escape(&scene.title))
+        .replace("/*WIDTH*/", &width.to_string())
+        .replace("/*HEIGHT*/", &height.to_string())
+        .replace("/*SCENE*/", &scene_json(scene))
+}
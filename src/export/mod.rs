@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::Path;
+
+use crate::axis::Axis;
+use crate::draw;
+use crate::draw::colors::{paper_rgb, series_rgb};
+use crate::draw::plot::{level_hue, Scales};
+use crate::interact::{AppState, View};
+use crate::threshold::Threshold;
+use crate::time;
+
+pub mod ansi;
+pub mod data;
+pub mod html;
+pub mod png;
+pub mod svg;
+
+/// How the samples of a series are drawn
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Mark {
+    Line,
+    Points,
+}
+
+pub struct Series {
+    pub name: String,
+    pub rgb: (u8, u8, u8),
+    /// (x, value) pairs, x being a timestamp or a sample index
+    pub points: Vec<(f64, f64)>,
+    pub mark: Mark,
+}
+
+/// The current view as graphics formats need it: what is plotted and over which ranges, in
+/// data coordinates
+pub struct Scene {
+    pub title: String,
+    pub scales: Scales,
+    pub series: Vec<Series>,
+    pub thresholds: Vec<Threshold>,
+}
+
+impl Scene {
+
+    /// Scene of the view on screen. Only the series and scatter views have one.
+    pub fn of(app: &AppState) -> Result<Scene, String> {
+        let scales = app.scales.ok_or("nothing drawn yet")?;
+        let file = Path::new(&app.source.path).file_name().map_or(String::new(), |f| f.to_string_lossy().into_owned());
+        let (title, series) = match app.view {
+            View::Series => {
+                let series = app.named_frames().into_iter().enumerate()
+                    .map(|(i, (name, frame))| {
+                        let (ini, _) = frame.range();
+                        let points = frame.data().iter().enumerate()
+                            .map(|(j, v)| (frame.time().map_or((ini + j) as f64, |t| t[j]), *v as f64))
+                            .collect();
+                        Series { name: name.to_string(), rgb: series_rgb(i), points, mark: Mark::Line }
+                    })
+                    .collect();
+                (file, series)
+            },
+            View::Scatter => {
+                let (names, pairs) = app.scatter_frames();
+                let series = names.into_iter().zip(pairs).enumerate()
+                    .map(|(i, (name, (x, y)))| {
+                        let points = x.data().iter().zip(y.data())
+                            .map(|(x, y)| (*x as f64, *y as f64))
+                            .collect();
+                        Series { name, rgb: series_rgb(i), points, mark: Mark::Points }
+                    })
+                    .collect();
+                (format!("{} scatter", file), series)
+            },
+            view => return Err(format!("the {} view can't be exported as graphics, only series and scatter", view.name())),
+        };
+        let thresholds = if app.view == View::Series { app.thresholds.clone() } else { Vec::new() };
+        Ok(Scene { title, scales, series, thresholds })
+    }
+
+    /// Axis range in the coordinate where the axis is linear
+    pub fn span(axis: Axis, range: (f64, f64)) -> (f64, f64) {
+        (axis.forward(range.0), axis.forward(range.1))
+    }
+
+}
+
+pub fn threshold_rgb(t: &Threshold) -> (u8, u8, u8) {
+    paper_rgb(level_hue(t.level))
+}
+
+/// File name for an export made now, `grust-<epoch seconds>.<ext>`
+pub fn default_path(ext: &str) -> String {
+    format!("grust-{}.{}", time::now() as u64, ext)
+}
+
+/// Pixel size given as `WxH`
+pub fn parse_size(s: &str) -> Option<(u32, u32)> {
+    let i = s.find('x')?;
+    let (w, h) = (s[..i].parse::<u32>().ok()?, s[i + 1..].parse::<u32>().ok()?);
+    if w >= 100 && h >= 100 && w <= 10000 && h <= 10000 { Some((w, h)) } else { None }
+}
+
+/// Writes the current view to `path`, in the format its extension names. Tells what was
+/// written.
+pub fn export(app: &AppState, path: &str, size: (u32, u32)) -> Result<String, String> {
+    let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    if matches!(&ext[..], "svg" | "png" | "html") && !matches!(app.view, View::Series | View::Scatter) {
+        return Err(format!("can't export the {} view as .{}, only as .ans or .txt", app.view.name(), ext))
+    }
+    let contents = match &ext[..] {
+        "svg" => svg::render(&Scene::of(app)?, size).into_bytes(),
+        "png" => png::render(&Scene::of(app)?, size)?,
+        "html" => html::render(&Scene::of(app)?, size).into_bytes(),
+        "csv" => data::csv(app)?.into_bytes(),
+        "jsonl" => data::jsonl(app)?.into_bytes(),
+        "ans" => ansi::render(&draw::screen_cells(), true).into_bytes(),
+        "txt" => ansi::render(&draw::screen_cells(), false).into_bytes(),
+        _ => return Err(format!("can't export to '{}', use .svg, .png, .html, .csv, .jsonl, .ans or .txt", path)),
+    };
+    fs::write(path, contents).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(format!("exported {}", path))
+}
@@ -0,0 +1,254 @@
+use super::{threshold_rgb, Mark, Scene};
+use crate::threshold::Limit;
+
+type Rgb = (u8, u8, u8);
+
+/// Room around the plot for the title, legend and tick labels, as in the SVG export
+const MARGIN: (f64, f64, f64, f64) = (56., 24., 48., 72.);
+
+const AXIS: Rgb = (0x44, 0x44, 0x44);
+const GRID: Rgb = (0xe4, 0xe4, 0xe4);
+const TEXT: Rgb = (0, 0, 0);
+
+/// Size of a font pixel for labels and the title
+const SMALL: f64 = 1.5;
+const LARGE: f64 = 2.;
+
+/// 5x7 glyphs for ASCII 32 to 126, one byte per column, lowest bit at the top
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5f, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], [0x24, 0x2a, 0x7f, 0x2a, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], [0x00, 0x1c, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1c, 0x00], [0x08, 0x2a, 0x1c, 0x2a, 0x08], [0x08, 0x08, 0x3e, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02], [0x3e, 0x51, 0x49, 0x45, 0x3e], [0x00, 0x42, 0x7f, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4b, 0x31], [0x18, 0x14, 0x12, 0x7f, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39], [0x3c, 0x4a, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1e], [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00], [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], [0x32, 0x49, 0x79, 0x41, 0x3e],
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], [0x7f, 0x49, 0x49, 0x49, 0x36], [0x3e, 0x41, 0x41, 0x41, 0x22],
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], [0x7f, 0x49, 0x49, 0x49, 0x41], [0x7f, 0x09, 0x09, 0x09, 0x01],
+    [0x3e, 0x41, 0x49, 0x49, 0x7a], [0x7f, 0x08, 0x08, 0x08, 0x7f], [0x00, 0x41, 0x7f, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3f, 0x01], [0x7f, 0x08, 0x14, 0x22, 0x41], [0x7f, 0x40, 0x40, 0x40, 0x40],
+    [0x7f, 0x02, 0x0c, 0x02, 0x7f], [0x7f, 0x04, 0x08, 0x10, 0x7f], [0x3e, 0x41, 0x41, 0x41, 0x3e],
+    [0x7f, 0x09, 0x09, 0x09, 0x06], [0x3e, 0x41, 0x51, 0x21, 0x5e], [0x7f, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31], [0x01, 0x01, 0x7f, 0x01, 0x01], [0x3f, 0x40, 0x40, 0x40, 0x3f],
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], [0x3f, 0x40, 0x38, 0x40, 0x3f], [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x07, 0x08, 0x70, 0x08, 0x07], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7f, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7f, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40], [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7f, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], [0x38, 0x44, 0x44, 0x48, 0x7f],
+    [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7e, 0x09, 0x01, 0x02], [0x0c, 0x52, 0x52, 0x52, 0x3e],
+    [0x7f, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7d, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3d, 0x00],
+    [0x7f, 0x10, 0x28, 0x44, 0x00], [0x00, 0x41, 0x7f, 0x40, 0x00], [0x7c, 0x04, 0x18, 0x04, 0x78],
+    [0x7c, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], [0x7c, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7c], [0x7c, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3f, 0x44, 0x40, 0x20], [0x3c, 0x40, 0x40, 0x20, 0x7c], [0x1c, 0x20, 0x40, 0x20, 0x1c],
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], [0x44, 0x28, 0x10, 0x28, 0x44], [0x0c, 0x50, 0x50, 0x50, 0x3c],
+    [0x44, 0x64, 0x54, 0x4c, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], [0x00, 0x00, 0x7f, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00], [0x08, 0x04, 0x08, 0x10, 0x08],
+];
+
+fn glyph(c: char) -> &'static [u8; 5] {
+    match c as u32 {
+        n @ 32..=126 => &FONT[n as usize - 32],
+        _ => &FONT['?' as usize - 32],
+    }
+}
+
+/// Where a text sits relative to the x it is drawn at
+#[derive(Copy,Clone)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// RGB pixels everything is blended on, white to start with. Drawing is anti-aliased by
+/// working out how much of each pixel a shape covers.
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    /// Drawing outside this rectangle, (left, top, right, bottom), is dropped
+    clip: (f64, f64, f64, f64),
+}
+
+impl Canvas {
+
+    fn new(width: usize, height: usize) -> Canvas {
+        Canvas { width, height, pixels: vec![255; width * height * 3], clip: (0., 0., width as f64, height as f64) }
+    }
+
+    /// Mixes `rgb` into the pixel at `x`, `y` by `alpha`, from 0 to 1
+    fn blend(&mut self, x: i64, y: i64, rgb: Rgb, alpha: f64) {
+        let (left, top, right, bottom) = self.clip;
+        let (fx, fy) = (x as f64 + 0.5, y as f64 + 0.5);
+        if alpha <= 0. || x < 0 || y < 0 || fx < left || fx > right || fy < top || fy > bottom { return }
+        let (x, y) = (x as usize, y as usize);
+        if x >= self.width || y >= self.height { return }
+        let i = (y * self.width + x) * 3;
+        let alpha = alpha.min(1.);
+        for (k, c) in [rgb.0, rgb.1, rgb.2].iter().enumerate() {
+            let old = self.pixels[i + k] as f64;
+            self.pixels[i + k] = (old + (*c as f64 - old) * alpha).round() as u8;
+        }
+    }
+
+    /// Segment `width` pixels thick with round ends. Each pixel gets the share of its center
+    /// that falls within the stroke.
+    fn line(&mut self, (x0, y0): (f64, f64), (x1, y1): (f64, f64), width: f64, rgb: Rgb) {
+        let r = width / 2.;
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let len2 = dx * dx + dy * dy;
+        let (xmin, xmax) = ((x0.min(x1) - r - 1.).floor() as i64, (x0.max(x1) + r + 1.).ceil() as i64);
+        let (ymin, ymax) = ((y0.min(y1) - r - 1.).floor() as i64, (y0.max(y1) + r + 1.).ceil() as i64);
+        for y in ymin.max(0)..=ymax.min(self.height as i64) {
+            for x in xmin.max(0)..=xmax.min(self.width as i64) {
+                let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+                let t = if len2 > 0. { (((px - x0) * dx + (py - y0) * dy) / len2).clamp(0., 1.) } else { 0. };
+                let (cx, cy) = (x0 + t * dx - px, y0 + t * dy - py);
+                let d = (cx * cx + cy * cy).sqrt();
+                self.blend(x, y, rgb, (r + 0.5 - d).clamp(0., 1.));
+            }
+        }
+    }
+
+    /// Axis aligned line of `on` pixel dashes with `off` pixel gaps
+    fn dashed(&mut self, from: (f64, f64), to: (f64, f64), (on, off): (f64, f64), rgb: Rgb) {
+        let len = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt();
+        if len <= 0. { return }
+        let at = |d: f64| (from.0 + (to.0 - from.0) * d / len, from.1 + (to.1 - from.1) * d / len);
+        let mut d = 0.;
+        while d < len {
+            self.line(at(d), at((d + on).min(len)), 1., rgb);
+            d += on + off;
+        }
+    }
+
+    fn disc(&mut self, (cx, cy): (f64, f64), r: f64, rgb: Rgb) {
+        self.line((cx, cy), (cx, cy), 2. * r, rgb);
+    }
+
+    fn text_width(s: &str, size: f64) -> f64 {
+        (6 * s.chars().count()) as f64 * size - size
+    }
+
+    /// Text with its baseline at `y`, each font pixel `size` screen pixels wide. Pixels are
+    /// shaded by the area the scaled glyphs cover in them.
+    fn text(&mut self, s: &str, (x, y): (f64, f64), size: f64, anchor: Anchor, rgb: Rgb) {
+        let width = Canvas::text_width(s, size);
+        let left = match anchor {
+            Anchor::Start => x,
+            Anchor::Middle => x - width / 2.,
+            Anchor::End => x - width,
+        };
+        let top = y - 7. * size;
+        let lit = |col: i64, row: i64| {
+            if col < 0 || !(0..7).contains(&row) || col % 6 == 5 { return false }
+            match s.chars().nth(col as usize / 6) {
+                Some(c) => glyph(c)[col as usize % 6] >> row & 1 == 1,
+                None => false,
+            }
+        };
+        for py in top.floor() as i64..=(top + 7. * size).ceil() as i64 {
+            for px in left.floor() as i64..=(left + width).ceil() as i64 {
+                // The pixel in font units
+                let (fx0, fx1) = ((px as f64 - left) / size, (px as f64 + 1. - left) / size);
+                let (fy0, fy1) = ((py as f64 - top) / size, (py as f64 + 1. - top) / size);
+                let mut area = 0.;
+                for row in fy0.floor() as i64..fy1.ceil() as i64 {
+                    for col in fx0.floor() as i64..fx1.ceil() as i64 {
+                        if !lit(col, row) { continue }
+                        let w = fx1.min(col as f64 + 1.) - fx0.max(col as f64);
+                        let h = fy1.min(row as f64 + 1.) - fy0.max(row as f64);
+                        area += w.max(0.) * h.max(0.);
+                    }
+                }
+                self.blend(px, py, rgb, area * size * size);
+            }
+        }
+    }
+
+}
+
+/// PNG image of the scene, `width` by `height` pixels, laid out like the SVG export
+pub fn render(scene: &Scene, (width, height): (u32, u32)) -> Result<Vec<u8>, String> {
+    let (top, right, bottom, left) = MARGIN;
+    let (w, h) = (width as f64 - left - right, height as f64 - top - bottom);
+    let sc = &scene.scales;
+    let (x0, x1) = Scene::span(sc.haxis, sc.x);
+    let (y0, y1) = Scene::span(sc.vaxis, sc.y);
+    let px = |x: f64| left + (sc.haxis.forward(x) - x0) / (x1 - x0) * w;
+    let py = |y: f64| top + h - (sc.vaxis.forward(y) - y0) / (y1 - y0) * h;
+
+    let mut canvas = Canvas::new(width as usize, height as usize);
+    canvas.text(&scene.title, (left, 20.), LARGE, Anchor::Start, TEXT);
+
+    // Legend
+    let mut x = left;
+    for series in &scene.series {
+        canvas.disc((x + 4., 38.), 4., series.rgb);
+        canvas.text(&series.name, (x + 12., 42.), SMALL, Anchor::Start, TEXT);
+        x += 24. + Canvas::text_width(&series.name, SMALL);
+    }
+
+    // Grid and ticks, then the axes over them
+    let (ystep, yticks) = sc.vaxis.ticks(sc.y.0, sc.y.1, (h / 50.).max(2.) as usize);
+    for v in yticks {
+        // Lines on pixel centers stay sharp
+        let y = py(v).floor() + 0.5;
+        if !(top - 0.5..=top + h + 0.5).contains(&y) { continue }
+        canvas.line((left, y), (left + w, y), 1., GRID);
+        canvas.line((left - 5., y), (left, y), 1., AXIS);
+        canvas.text(&sc.vaxis.label(v, ystep), (left - 8., y + 4.), SMALL, Anchor::End, TEXT);
+    }
+    let (xstep, xticks) = sc.haxis.ticks(sc.x.0, sc.x.1, (w / 100.).max(2.) as usize);
+    for v in xticks {
+        let x = px(v).floor() + 0.5;
+        if !(left - 0.5..=left + w + 0.5).contains(&x) { continue }
+        canvas.line((x, top), (x, top + h), 1., GRID);
+        canvas.line((x, top + h), (x, top + h + 5.), 1., AXIS);
+        canvas.text(&sc.haxis.label(v, xstep), (x, top + h + 20.), SMALL, Anchor::Middle, TEXT);
+    }
+    canvas.line((left, top), (left, top + h), 1., AXIS);
+    canvas.line((left, top + h), (left + w, top + h), 1., AXIS);
+
+    canvas.clip = (left, top, left + w, top + h);
+    for t in &scene.thresholds {
+        let (from, to) = match t.limit {
+            Limit::Above(v) | Limit::Below(v) => ((left, py(v)), (left + w, py(v))),
+            Limit::At(x) => ((px(x), top), (px(x), top + h)),
+        };
+        canvas.dashed(from, to, (6., 4.), threshold_rgb(t));
+    }
+    for series in &scene.series {
+        let points = series.points.iter().map(|(x, y)| (px(*x), py(*y)));
+        match series.mark {
+            Mark::Line => {
+                // Values the axis can't show split the line
+                let mut last = None;
+                for p in points {
+                    if !(p.0.is_finite() && p.1.is_finite()) { last = None; continue }
+                    if let Some(from) = last { canvas.line(from, p, 1.5, series.rgb) }
+                    last = Some(p);
+                }
+            },
+            Mark::Points => {
+                for p in points.filter(|(x, y)| x.is_finite() && y.is_finite()) {
+                    canvas.disc(p, 2., series.rgb);
+                }
+            },
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = ::png::Encoder::new(&mut png, width, height);
+    encoder.set_color(::png::ColorType::Rgb);
+    encoder.set_depth(::png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(&canvas.pixels).map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(png)
+}
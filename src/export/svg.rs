@@ -0,0 +1,101 @@
+use std::fmt::Write;
+
+use super::{threshold_rgb, Mark, Scene};
+use crate::threshold::Limit;
+
+/// Room around the plot for the title, legend and tick labels
+const MARGIN: (f64, f64, f64, f64) = (56., 24., 48., 72.);
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn color((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Standalone SVG document of the scene, `width` by `height` pixels
+pub fn render(scene: &Scene, (width, height): (u32, u32)) -> String {
+    let (top, right, bottom, left) = MARGIN;
+    let (w, h) = (width as f64 - left - right, height as f64 - top - bottom);
+    let sc = &scene.scales;
+    let (x0, x1) = Scene::span(sc.haxis, sc.x);
+    let (y0, y1) = Scene::span(sc.vaxis, sc.y);
+    let px = |x: f64| left + (sc.haxis.forward(x) - x0) / (x1 - x0) * w;
+    let py = |y: f64| top + h - (sc.vaxis.forward(y) - y0) / (y1 - y0) * h;
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="sans-serif" font-size="12">"#,
+                     width, height, width, height);
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    let _ = writeln!(svg, r#"<defs><clipPath id="plot"><rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}"/></clipPath></defs>"#,
+                     left, top, w, h);
+    let _ = writeln!(svg, r#"<text x="{:.1}" y="20" font-size="15" font-weight="bold">{}</text>"#, left, escape(&scene.title));
+
+    // Legend
+    let mut x = left;
+    for series in &scene.series {
+        let _ = writeln!(svg, r#"<circle cx="{:.1}" cy="38" r="4" fill="{}"/><text x="{:.1}" y="42">{}</text>"#,
+                         x + 4., color(series.rgb), x + 12., escape(&series.name));
+        x += 24. + 7. * series.name.chars().count() as f64;
+    }
+
+    // Axes, ticks and grid
+    let _ = writeln!(svg, r##"<g stroke="#444" fill="none"><path d="M{:.1} {:.1}V{:.1}H{:.1}"/></g>"##, left, top, top + h, left + w);
+    let (ystep, yticks) = sc.vaxis.ticks(sc.y.0, sc.y.1, (h / 50.).max(2.) as usize);
+    for v in yticks {
+        let y = py(v);
+        if !(top - 0.5..=top + h + 0.5).contains(&y) { continue }
+        let _ = writeln!(svg, r##"<path d="M{:.1} {:.1}H{:.1}" stroke="#e4e4e4"/><path d="M{:.1} {:.1}h-5" stroke="#444"/>"##,
+                         left, y, left + w, left, y);
+        let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"#, left - 8., y + 4., escape(&sc.vaxis.label(v, ystep)));
+    }
+    let (xstep, xticks) = sc.haxis.ticks(sc.x.0, sc.x.1, (w / 100.).max(2.) as usize);
+    for v in xticks {
+        let x = px(v);
+        if !(left - 0.5..=left + w + 0.5).contains(&x) { continue }
+        let _ = writeln!(svg, r##"<path d="M{:.1} {:.1}V{:.1}" stroke="#e4e4e4"/><path d="M{:.1} {:.1}v5" stroke="#444"/>"##,
+                         x, top, top + h, x, top + h);
+        let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#, x, top + h + 20., escape(&sc.haxis.label(v, xstep)));
+    }
+
+    let _ = writeln!(svg, r#"<g clip-path="url(#plot)">"#);
+    for t in &scene.thresholds {
+        let stroke = color(threshold_rgb(t));
+        match t.limit {
+            Limit::Above(v) | Limit::Below(v) => {
+                let _ = writeln!(svg, r#"<path d="M{:.1} {:.1}H{:.1}" stroke="{}" stroke-dasharray="6 4"/>"#, left, py(v), left + w, stroke);
+            },
+            Limit::At(x) => {
+                let _ = writeln!(svg, r#"<path d="M{:.1} {:.1}V{:.1}" stroke="{}" stroke-dasharray="6 4"/>"#, px(x), top, top + h, stroke);
+            },
+        }
+    }
+    for series in &scene.series {
+        let stroke = color(series.rgb);
+        let points = series.points.iter().map(|(x, y)| (px(*x), py(*y)));
+        match series.mark {
+            Mark::Line => {
+                // Values the axis can't show split the line
+                let mut path = String::new();
+                let mut pen_down = false;
+                for (x, y) in points {
+                    if !(x.is_finite() && y.is_finite()) { pen_down = false; continue }
+                    let _ = write!(path, "{}{:.1} {:.1}", if pen_down { "L" } else { "M" }, x, y);
+                    pen_down = true;
+                }
+                let _ = writeln!(svg, r#"<path d="{}" fill="none" stroke="{}" stroke-width="1.5" stroke-linejoin="round"/>"#, path, stroke);
+            },
+            Mark::Points => {
+                let _ = writeln!(svg, r#"<g fill="{}">"#, stroke);
+                for (x, y) in points.filter(|(x, y)| x.is_finite() && y.is_finite()) {
+                    let _ = writeln!(svg, r#"<circle cx="{:.1}" cy="{:.1}" r="2"/>"#, x, y);
+                }
+                let _ = writeln!(svg, "</g>");
+            },
+        }
+    }
+    let _ = writeln!(svg, "</g>");
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,375 @@
+use crate::stream::{Stream, StreamBundle};
+
+/// Formula over the columns of a bundle, such as `a/b*100` or `max(abs(x), 1)`. Numbers,
+/// column names (letters, digits, `_` and `.`), `+ - * / ^`, parentheses and the functions
+/// abs, sqrt, exp, ln, log10, min, max and pow.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Col(String),
+    Neg(Box<Expr>),
+    Bin(char, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Func {
+    Abs,
+    Sqrt,
+    Exp,
+    Ln,
+    Log10,
+    Min,
+    Max,
+    Pow,
+}
+
+impl Func {
+
+    pub fn from(s: &str) -> Option<Func> {
+        match s {
+            "abs" => Some(Func::Abs),
+            "sqrt" => Some(Func::Sqrt),
+            "exp" => Some(Func::Exp),
+            "ln" | "log" => Some(Func::Ln),
+            "log10" => Some(Func::Log10),
+            "min" => Some(Func::Min),
+            "max" => Some(Func::Max),
+            "pow" => Some(Func::Pow),
+            _ => None
+        }
+    }
+
+    /// Arguments taken, min and max fold over any number of them
+    fn arity(self) -> Option<usize> {
+        match self {
+            Func::Min | Func::Max => None,
+            Func::Pow => Some(2),
+            _ => Some(1),
+        }
+    }
+
+    fn apply(self, args: &[f64]) -> f64 {
+        match self {
+            Func::Abs => args[0].abs(),
+            Func::Sqrt => args[0].sqrt(),
+            Func::Exp => args[0].exp(),
+            Func::Ln => args[0].ln(),
+            Func::Log10 => args[0].log10(),
+            Func::Min => args.iter().cloned().fold(f64::INFINITY, f64::min),
+            Func::Max => args.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            Func::Pow => args[0].powf(args[1]),
+        }
+    }
+
+}
+
+#[derive(Clone,Debug,PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(char),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some(&(i, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        }
+        else if c.is_ascii_digit() || c == '.' {
+            let mut end = i;
+            while let Some(&(j, d)) = chars.peek() {
+                let exponent = (d == '-' || d == '+') && s[..j].ends_with(['e', 'E']);
+                if !(d.is_ascii_alphanumeric() || d == '.' || exponent) { break }
+                end = j + d.len_utf8();
+                chars.next();
+            }
+            let num = &s[i..end];
+            tokens.push(Token::Num(num.parse().map_err(|_| format!("bad number '{}'", num))?));
+        }
+        else if c.is_alphabetic() || c == '_' {
+            let mut end = i;
+            while let Some(&(j, d)) = chars.peek() {
+                if !(d.is_alphanumeric() || d == '_' || d == '.') { break }
+                end = j + d.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Ident(s[i..end].to_string()));
+        }
+        else {
+            tokens.push(match c {
+                '+' | '-' | '*' | '/' | '^' => Token::Op(c),
+                '(' => Token::Open,
+                ')' => Token::Close,
+                ',' => Token::Comma,
+                _ => return Err(format!("unexpected '{}'", c)),
+            });
+            chars.next();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens, one method per precedence level
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), String> {
+        if self.next() == Some(token) { Ok(()) } else { Err(format!("expected {}", what)) }
+    }
+
+    /// sum := term (('+' | '-') term)*
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        while let Some(Token::Op(op @ '+')) | Some(Token::Op(op @ '-')) = self.peek().cloned() {
+            self.next();
+            lhs = Expr::Bin(op, Box::new(lhs), Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    /// term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op @ '*')) | Some(Token::Op(op @ '/')) = self.peek().cloned() {
+            self.next();
+            lhs = Expr::Bin(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    /// unary := '-' unary | power
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Op('-')) {
+            self.next();
+            return Ok(Expr::Neg(Box::new(self.unary()?)))
+        }
+        self.power()
+    }
+
+    /// power := atom ('^' unary)?, right associative
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if self.peek() == Some(&Token::Op('^')) {
+            self.next();
+            return Ok(Expr::Bin('^', Box::new(base), Box::new(self.unary()?)))
+        }
+        Ok(base)
+    }
+
+    /// atom := number | column | function '(' sum (',' sum)* ')' | '(' sum ')'
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(v)) => Ok(Expr::Num(v)),
+            Some(Token::Open) => {
+                let inner = self.sum()?;
+                self.expect(Token::Close, "')'")?;
+                Ok(inner)
+            },
+            Some(Token::Ident(name)) => {
+                if self.peek() != Some(&Token::Open) { return Ok(Expr::Col(name)) }
+                let func = Func::from(&name).ok_or(format!("unknown function '{}'", name))?;
+                self.next();
+                let mut args = vec![self.sum()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.next();
+                    args.push(self.sum()?);
+                }
+                self.expect(Token::Close, "')'")?;
+                match func.arity() {
+                    Some(n) if n != args.len() => Err(format!("{} takes {} argument(s)", name, n)),
+                    _ => Ok(Expr::Call(func, args)),
+                }
+            },
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err(String::from("unexpected end of formula")),
+        }
+    }
+
+}
+
+impl Expr {
+
+    pub fn parse(s: &str) -> Result<Expr, String> {
+        let mut parser = Parser { tokens: tokenize(s)?, pos: 0 };
+        let expr = parser.sum()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+        }
+    }
+
+    /// Column names used, in order of appearance
+    pub fn columns(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_columns(&mut names);
+        names
+    }
+
+    fn collect_columns<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Expr::Num(_) => {},
+            Expr::Col(name) => if !names.contains(&name.as_str()) { names.push(name) },
+            Expr::Neg(e) => e.collect_columns(names),
+            Expr::Bin(_, a, b) => { a.collect_columns(names); b.collect_columns(names) },
+            Expr::Call(_, args) => for a in args { a.collect_columns(names) },
+        }
+    }
+
+    /// Value for one sample, `col` giving the value of each column
+    pub fn eval(&self, col: &dyn Fn(&str) -> f64) -> f64 {
+        match self {
+            Expr::Num(v) => *v,
+            Expr::Col(name) => col(name),
+            Expr::Neg(e) => -e.eval(col),
+            Expr::Bin(op, a, b) => {
+                let (a, b) = (a.eval(col), b.eval(col));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    _ => a.powf(b),
+                }
+            },
+            Expr::Call(func, args) => {
+                let args: Vec<f64> = args.iter().map(|a| a.eval(col)).collect();
+                func.apply(&args)
+            },
+        }
+    }
+
+}
+
+/// A series computed with a formula, `NAME = EXPR`
+#[derive(Clone,Debug)]
+pub struct Computed {
+    pub name: String,
+    pub expr: Expr,
+}
+
+impl Computed {
+
+    pub fn parse(s: &str) -> Result<Computed, String> {
+        let i = s.find('=').ok_or(format!("expected NAME=EXPR, got '{}'", s))?;
+        let name = s[..i].trim();
+        if name.is_empty() { return Err(format!("missing name in '{}'", s)) }
+        Ok(Computed { name: name.to_string(), expr: Expr::parse(&s[i + 1..])? })
+    }
+
+    /// Evaluates the formula sample by sample, over as many samples as the shortest column has.
+    pub fn stream(&self, bundle: &StreamBundle<f32>) -> Result<Stream<f32>, String> {
+        let names = self.expr.columns();
+        let mut inputs = Vec::new();
+        for name in &names {
+            inputs.push(bundle.get(name).ok_or(format!("no column '{}'", name))?);
+        }
+        let len = inputs.iter().map(|s| s.len()).min().unwrap_or(0);
+        if len == 0 && !inputs.is_empty() { return Err(format!("no samples for {}", self.name)) }
+        // A formula of constants alone is a flat line as long as the input
+        let len = if inputs.is_empty() { bundle.streams().first().map_or(0, |s| s.len()) } else { len };
+        if len == 0 { return Err(String::from("no samples")) }
+
+        let values = (0..len).map(|i| {
+            let col = |name: &str| names.iter().position(|n| *n == name)
+                .map_or(f64::NAN, |k| inputs[k].data()[i] as f64);
+            let v = self.expr.eval(&col);
+            // No value for this sample, as from a division by zero, rather than an infinity
+            if v.is_finite() { v as f32 } else { f32::NAN }
+        });
+        let time = inputs.first().cloned().or_else(|| bundle.streams().first()).and_then(|s| s.time());
+        Ok(match time {
+            Some(time) => Stream::with_time(values, time[..len].to_vec()),
+            None => Stream::new(values),
+        })
+    }
+
+}
+
+/// Computes every formula over the bundle and puts the result in it, replacing the previous
+/// one. Formulas that can't be evaluated are reported and left out.
+pub fn update(bundle: &mut StreamBundle<f32>, computed: &[Computed]) -> Result<(), String> {
+    let mut errors = Vec::new();
+    for c in computed {
+        match c.stream(bundle) {
+            Ok(stream) => bundle.set(&c.name, stream),
+            Err(e) => errors.push(format!("{}: {}", c.name, e)),
+        }
+    }
+    if errors.is_empty() { Ok(()) } else { Err(errors.join(", ")) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(s: &str) -> f64 {
+        let col = |name: &str| match name { "a" => 6., "b.x" => 3., _ => f64::NAN };
+        Expr::parse(s).unwrap().eval(&col)
+    }
+
+    #[test]
+    fn precedence_and_unary_minus() {
+        assert_eq!(eval("1 + 2 * 3"), 7.);
+        assert_eq!(eval("(1 + 2) * 3"), 9.);
+        assert_eq!(eval("a / b.x * 100"), 200.);
+        assert_eq!(eval("10 - 4 - 3"), 3.);
+        assert_eq!(eval("2 ^ 3 ^ 2"), 512.);
+        assert_eq!(eval("-2 ^ 2"), -4.);
+        assert_eq!(eval("2 ^ -1"), 0.5);
+        assert_eq!(eval("a * -b.x"), -18.);
+        assert_eq!(eval("--a"), 6.);
+        assert_eq!(eval("1.5e-1 * 10"), 1.5);
+        assert_eq!(eval("max(abs(-a), b.x, 1) + min(a)"), 12.);
+        assert_eq!(eval("pow(b.x, 2) + log10(100)"), 11.);
+        assert_eq!(Expr::parse("max(a, b.x) / a").unwrap().columns(), ["a", "b.x"]);
+    }
+
+    #[test]
+    fn formula_errors() {
+        let error = |s: &str| Expr::parse(s).unwrap_err();
+        assert_eq!(error("1 +"), "unexpected end of formula");
+        assert_eq!(error("(1 + 2"), "expected ')'");
+        assert_eq!(error("1 2"), "unexpected Num(2.0)");
+        assert_eq!(error("a % 2"), "unexpected '%'");
+        assert_eq!(error("1.2.3"), "bad number '1.2.3'");
+        assert_eq!(error("cos(a)"), "unknown function 'cos'");
+        assert_eq!(error("pow(a)"), "pow takes 2 argument(s)");
+        assert_eq!(Computed::parse("= a").unwrap_err(), "missing name in '= a'");
+        assert_eq!(Computed::parse("a + 1").unwrap_err(), "expected NAME=EXPR, got 'a + 1'");
+    }
+
+    #[test]
+    fn samples_without_a_value_are_nan() {
+        let mut bundle = StreamBundle::new();
+        bundle.push("a", Stream::new(vec![6f32, 6., 0.].into_iter()));
+        bundle.push("b", Stream::new(vec![3f32, 0., 0.].into_iter()));
+        let ratio = Computed::parse("r = a / b").unwrap().stream(&bundle).unwrap();
+        assert_eq!(ratio.data()[0], 2.);
+        assert!(ratio.data()[1].is_nan() && ratio.data()[2].is_nan());
+        // Left out of the range rather than counted as zeros
+        let frame = ratio.frame(0, 3);
+        assert_eq!((frame.min(), frame.max()), (2., 2.));
+        let stats = frame.stats();
+        assert_eq!((stats.count, stats.mean, stats.std_dev, stats.median), (1, 2., 0., 2.));
+    }
+}
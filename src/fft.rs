@@ -0,0 +1,265 @@
+use std::f64::consts::PI;
+
+use crate::stream::{Frame, Matrix};
+
+/// Taper applied to a block of samples before transforming it, trading frequency resolution
+/// for less leakage between bins
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum WindowFunction {
+    Hann,
+    Hamming,
+    None,
+}
+
+impl WindowFunction {
+
+    pub fn from(s: &str) -> Option<WindowFunction> {
+        match s {
+            "hann" | "hanning" => Some(WindowFunction::Hann),
+            "hamming" => Some(WindowFunction::Hamming),
+            "none" | "rect" => Some(WindowFunction::None),
+            _ => None
+        }
+    }
+
+    pub fn next(self) -> WindowFunction {
+        match self {
+            WindowFunction::Hann => WindowFunction::Hamming,
+            WindowFunction::Hamming => WindowFunction::None,
+            WindowFunction::None => WindowFunction::Hann,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WindowFunction::Hann => "hann",
+            WindowFunction::Hamming => "hamming",
+            WindowFunction::None => "none",
+        }
+    }
+
+    /// Weights of the window over `n` samples
+    pub fn weights(self, n: usize) -> Vec<f64> {
+        let denom = (n.max(2) - 1) as f64;
+        (0..n).map(|i| {
+            let phase = 2. * PI * i as f64 / denom;
+            match self {
+                WindowFunction::Hann => 0.5 - 0.5 * phase.cos(),
+                WindowFunction::Hamming => 0.54 - 0.46 * phase.cos(),
+                WindowFunction::None => 1.,
+            }
+        }).collect()
+    }
+
+}
+
+/// In-place radix-2 transform of `re` + i`im`, whose length must be a power of two
+pub fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    if n < 2 { return }
+
+    // Bit reversed reordering
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2. * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Amplitude of each frequency from 0 up to Nyquist
+pub struct Spectrum {
+    pub freqs: Vec<f64>,
+    pub magnitude: Vec<f64>,
+}
+
+impl Spectrum {
+
+    /// Spectrum of `samples` taken `rate` times per second. The mean is removed so the DC bin
+    /// doesn't dwarf everything else, and the samples are zero padded to a power of two.
+    /// Amplitudes are scaled so a sine of amplitude A peaks at about A whatever the window.
+    pub fn of(samples: &[f32], rate: f64, window: WindowFunction) -> Spectrum {
+        let n = samples.len();
+        if n < 2 { return Spectrum { freqs: Vec::new(), magnitude: Vec::new() } }
+
+        let mean = samples.iter().map(|v| *v as f64).sum::<f64>() / n as f64;
+        let weights = window.weights(n);
+        let size = n.next_power_of_two();
+        let mut re = vec![0.; size];
+        let mut im = vec![0.; size];
+        for (i, (v, w)) in samples.iter().zip(&weights).enumerate() {
+            re[i] = (*v as f64 - mean) * w;
+        }
+        fft(&mut re, &mut im);
+
+        let gain = 2. / weights.iter().sum::<f64>();
+        let bins = size / 2 + 1;
+        Spectrum {
+            freqs: (0..bins).map(|k| k as f64 * rate / size as f64).collect(),
+            magnitude: (0..bins).map(|k| re[k].hypot(im[k]) * gain).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.freqs.is_empty()
+    }
+
+    pub fn max(&self) -> f64 {
+        self.magnitude.iter().cloned().fold(0., f64::max)
+    }
+
+    /// Frequency of the strongest bin
+    pub fn peak(&self) -> Option<f64> {
+        self.magnitude.iter().enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, _)| self.freqs[i])
+    }
+
+}
+
+/// Samples per second of a frame: the given rate, one over the median spacing of timed frames,
+/// or one per index otherwise
+pub fn sample_rate(frame: &Frame<f32>, rate: Option<f64>) -> f64 {
+    if let Some(rate) = rate { return rate }
+    let time = match frame.time() {
+        Some(time) if time.len() > 1 => time,
+        _ => return 1.,
+    };
+    let mut steps: Vec<f64> = time.windows(2).map(|w| w[1] - w[0]).filter(|d| *d > 0.).collect();
+    if steps.is_empty() { return 1. }
+    steps.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    1. / steps[steps.len() / 2]
+}
+
+/// Short-time spectra over sliding windows: a column per window, a row per frequency bin, in
+/// decibels. Windows start at multiples of `hop` counted from the start of the stream, so a
+/// growing stream scrolls by whole columns instead of shimmering.
+pub struct Spectrogram {
+    pub matrix: Matrix,
+    /// Index into the samples where each column's window starts
+    pub starts: Vec<usize>,
+    pub nyquist: f64,
+}
+
+impl Spectrogram {
+
+    /// Up to `columns` of the latest windows of `size` samples. `offset` is the stream index of
+    /// the first sample.
+    pub fn of(samples: &[f32], offset: usize, rate: f64, window: WindowFunction,
+              size: usize, hop: usize, columns: usize) -> Spectrogram {
+        let n = samples.len();
+        let hop = hop.max(1);
+        let bins = size / 2 + 1;
+        let mut starts = Vec::new();
+        if n >= size && size >= 2 {
+            let mut start = (offset + n - size) / hop * hop;
+            while start >= offset && starts.len() < columns {
+                starts.push(start - offset);
+                if start < hop { break }
+                start -= hop;
+            }
+            starts.reverse();
+        }
+
+        let mut matrix = Matrix::new(starts.len(), bins);
+        for (x, start) in starts.iter().enumerate() {
+            let spectrum = Spectrum::of(&samples[*start..*start + size], rate, window);
+            for (y, m) in spectrum.magnitude.iter().enumerate() {
+                matrix.set(x, y, (20. * (m + 1e-12).log10()) as f32);
+            }
+        }
+        Spectrogram { matrix, starts, nyquist: rate / 2. }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::Stream;
+
+    #[test]
+    fn sine_peaks_at_its_frequency_and_amplitude() {
+        // 8 Hz at 64 samples per second, falling right on a bin
+        let (rate, freq, amplitude) = (64., 8., 3.);
+        let samples: Vec<f32> = (0..256)
+            .map(|i| (amplitude * (2. * PI * freq * i as f64 / rate).sin() + 1.) as f32)
+            .collect();
+        for window in &[WindowFunction::Hann, WindowFunction::Hamming, WindowFunction::None] {
+            let spectrum = Spectrum::of(&samples, rate, *window);
+            assert_eq!(spectrum.freqs.len(), 129);
+            assert_eq!(spectrum.peak(), Some(freq), "{}", window.name());
+            assert!((spectrum.max() - amplitude).abs() < 0.05, "{} {}", window.name(), spectrum.max());
+            // The mean is taken out
+            assert!(spectrum.magnitude[0] < 1e-3, "{} {}", window.name(), spectrum.magnitude[0]);
+        }
+        assert!(Spectrum::of(&[1.], rate, WindowFunction::Hann).is_empty());
+    }
+
+    #[test]
+    fn rate_from_the_median_spacing() {
+        let time = vec![0., 0.1, 0.2, 0.5, 0.6, 0.7, 0.7];
+        let stream: Stream<f32> = Stream::with_time(vec![0f32; 7].into_iter(), time);
+        let rate = sample_rate(&stream.frame(0, 7), None);
+        assert!((rate - 10.).abs() < 1e-9, "{}", rate);
+        assert_eq!(sample_rate(&stream.frame(0, 7), Some(250.)), 250.);
+        assert_eq!(sample_rate(&stream.frame(0, 1), None), 1.);
+        let untimed: Stream<f32> = Stream::new(vec![0f32; 4].into_iter());
+        assert_eq!(sample_rate(&untimed.frame(0, 4), None), 1.);
+    }
+
+    #[test]
+    fn spectrogram_windows_start_on_multiples_of_hop() {
+        let starts = |offset: usize, n: usize, size: usize, hop: usize, columns: usize| {
+            Spectrogram::of(&vec![0.; n], offset, 1., WindowFunction::Hann, size, hop, columns).starts
+        };
+        assert_eq!(starts(0, 8, 8, 4, 10), [0]);
+        assert_eq!(starts(0, 7, 8, 4, 10), [] as [usize; 0]);
+        // Counted from the start of the stream, so 4 at offset 5 is gone already
+        assert_eq!(starts(5, 20, 8, 4, 10), [3, 7, 11]);
+        assert_eq!(starts(5, 8, 8, 4, 10), [] as [usize; 0]);
+        assert_eq!(starts(4, 8, 8, 4, 10), [0]);
+        // The latest ones when there are more than columns
+        assert_eq!(starts(0, 40, 8, 4, 3), [24, 28, 32]);
+        assert_eq!(starts(0, 10, 8, 0, 10), [0, 1, 2]);
+
+        // As the stream grows, windows keep their places and the newest is within a hop of the end
+        for n in 8..40 {
+            for offset in 0..3 {
+                let s = starts(offset, n, 8, 4, 100);
+                assert!(s.iter().all(|start| (start + offset) % 4 == 0), "{} {} {:?}", offset, n, s);
+                assert!(s.windows(2).all(|w| w[1] == w[0] + 4));
+                if let Some(last) = s.last() { assert!(last + 8 <= n && last + 8 + 4 > n, "{} {} {:?}", offset, n, s) }
+            }
+        }
+    }
+}
@@ -1,13 +1,31 @@
-use ncurses::getch;
-use ncurses::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path;
 use std::char::{self};
 use std::str::FromStr;
 use std::fmt::Debug;
 
+use std::io;
+use std::sync::mpsc::Receiver;
+
+use crate::alert::Trigger;
+use crate::args::Args;
+use crate::axis::Axis;
+use crate::derive::{self, Derived};
 use crate::draw;
+use crate::draw::backend::{self, Input};
+use crate::draw::heatmap::Palette;
+use crate::draw::keys::*;
+use crate::draw::plot::{Orientation, Scales};
+use crate::draw::theme::{self, Theme};
+use crate::export;
+use crate::expr::{self, Computed};
+use crate::fft::WindowFunction;
+use crate::source::{Row, Source};
+use crate::stream::{Bins, Frame, FramePair, Stream, StreamBundle};
+use crate::threshold::{Level, Threshold};
+use crate::time::Timestamp;
+use crate::yrange::{self, RangePolicy, YRange};
 
 // use back::{*, Mode, State};
 use draw::{*}; //DrawResult;
@@ -29,28 +47,410 @@ mod vars {
 } /* vars */
 
 
-pub fn interact() -> DrawResult {
+pub fn interact(app: &mut AppState) -> DrawResult {
 
-    // refresh();
-    let ch = getch() as u8 as char;
+    // Input times out while following a file, so new samples get drawn
+    let code = match draw::poll_input() {
+        Some(Input::Key(code)) => code,
+        Some(Input::Click(yx)) => { app.click(yx); return Ok(()) },
+        // The next frame gets drawn at the new size
+        Some(Input::Resize(_)) | None => return Ok(()),
+    };
+    if let State::Command(_) = app.state { return command_key(app, code) }
+    let ch = code as u8 as char;
     let YX(height, width) = screen_size() - YX(2,0);
 
     match ch {
         'q' => {println!("EXIT"); Err(DrawError::CleanExit)},
-        'w' => {Ok(())},
-         _  => {mvprintw(height+1i32,width-20i32, &format!("{:?}\n",ch)); Ok(())},
+        ':' => {app.state = State::Command(String::new()); Ok(())},
+        'w' => {app.window = app.window.next(); Ok(())},
+        'x' if !app.scatter.is_empty() => {app.toggle_view(View::Scatter); Ok(())},
+        'd' => {app.density = !app.density; Ok(())},
+        's' => {app.stats = !app.stats; Ok(())},
+        'h' => {app.toggle_view(View::Histogram); Ok(())},
+        'b' => {app.toggle_view(View::Bars); Ok(())},
+        'm' => {app.toggle_view(View::Heatmap); Ok(())},
+        'p' => {app.palette = app.palette.next(); Ok(())},
+        't' => {
+            let _ = app.switch_theme(None);
+            app.message = Some(format!("theme: {}", app.themes[app.theme].name));
+            Ok(())
+        },
+        'f' => {app.toggle_view(View::Spectrum); Ok(())},
+        'g' => {app.toggle_view(View::Spectrogram); Ok(())},
+        'F' => {app.log_freq = !app.log_freq; Ok(())},
+        'y' => {app.cycle_yrange(); Ok(())},
+        'l' => {
+            app.yscale = app.yscale.next_scale();
+            app.message = Some(format!("y scale: {}", app.yscale.name()));
+            Ok(())
+        },
+        'e' => {app.message = Some(app.export(&export::default_path("svg"), None)); Ok(())},
+        'r' => {app.orientation = app.orientation.flip(); Ok(())},
+        '\t' => {app.select_next(); Ok(())},
+         _  => {backend::with(|b| b.print(YX(height+1i32,width-20i32), &format!("{:?}",ch))); Ok(())},
         // _ => Ok(())
     }
 
 }
 
+/// Keys typed at the `:` prompt, until Enter runs them or Escape drops them
+fn command_key(app: &mut AppState, code: i32) -> DrawResult {
+    let line = match app.state {
+        State::Command(ref mut line) => line,
+        State::Normal => return Ok(()),
+    };
+    match code {
+        27 => app.state = State::Normal,
+        10 | 13 | KEY_ENTER => {
+            let line = line.trim().to_string();
+            app.state = State::Normal;
+            return run_command(app, &line)
+        },
+        8 | 127 | KEY_BACKSPACE => { line.pop(); },
+        32..=126 => line.push(code as u8 as char),
+        _ => {},
+    }
+    Ok(())
+}
+
+/// Runs a command line: `let NAME = EXPR` adds a computed series, `export [FILE] [WxH]` saves
+/// the view, `theme [NAME]` changes the colors, `q` quits
+fn run_command(app: &mut AppState, line: &str) -> DrawResult {
+    let (cmd, rest) = match line.find(' ') {
+        Some(i) => (&line[..i], line[i + 1..].trim()),
+        None => (line, ""),
+    };
+    app.message = match cmd {
+        "" => None,
+        "q" | "quit" => return Err(DrawError::CleanExit),
+        "let" => match Computed::parse(rest) {
+            Ok(computed) => app.compute(computed).err(),
+            Err(e) => Some(e),
+        },
+        "theme" => match app.switch_theme(Some(rest).filter(|r| !r.is_empty())) {
+            Ok(()) => Some(format!("theme: {}", app.themes[app.theme].name)),
+            Err(e) => Some(e),
+        },
+        "export" => {
+            // export [FILE] [WxH]
+            let mut words = rest.split_whitespace();
+            let path = words.next().map_or_else(|| export::default_path("svg"), String::from);
+            match words.next().map(|s| export::parse_size(s).ok_or(format!("bad size '{}', expected WxH", s))) {
+                Some(Err(e)) => Some(e),
+                size => Some(app.export(&path, size.and_then(Result::ok))),
+            }
+        },
+        _ => Some(format!("unknown command '{}'", cmd)),
+    };
+    Ok(())
+}
+
 pub enum State {
-    Normal
+    Normal,
+    /// Typing a command, the line so far
+    Command(String),
+}
+
+/// What the main window shows
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
+pub enum View {
+    Series,
+    Scatter,
+    Histogram,
+    Bars,
+    Heatmap,
+    Spectrum,
+    Spectrogram,
+}
+
+impl View {
+
+    /// Views with a value axis, by name
+    pub fn from(s: &str) -> Option<View> {
+        match s {
+            "series" => Some(View::Series),
+            "bars" => Some(View::Bars),
+            "heatmap" => Some(View::Heatmap),
+            "spectrum" => Some(View::Spectrum),
+            _ => None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            View::Series => "series",
+            View::Scatter => "scatter",
+            View::Histogram => "histogram",
+            View::Bars => "bars",
+            View::Heatmap => "heatmap",
+            View::Spectrum => "spectrum",
+            View::Spectrogram => "spectrogram",
+        }
+    }
+
 }
 
 pub struct AppState {
     pub state: State,
-    pub mainw: Window
+    pub source: Source,
+    pub bundle: StreamBundle<f32>,
+    /// New rows of the followed file
+    pub feed: Option<Receiver<Row>>,
+    pub from: Option<Timestamp>,
+    pub to: Option<Timestamp>,
+    pub view: View,
+    /// Column names plotted against each other in the scatter view
+    pub scatter: Vec<(String, String)>,
+    pub density: bool,
+    /// Series computed from the input columns, kept in the bundle after them
+    pub derived: Vec<Derived>,
+    /// Series defined by formulas, after the derived ones
+    pub computed: Vec<Computed>,
+    /// Reference lines, horizontal ones watching the last sample of the series
+    pub thresholds: Vec<Threshold>,
+    /// Thresholds crossed by the last sample, as (threshold, series) indices
+    crossed: HashSet<(usize, usize)>,
+    /// Command run on threshold crossings
+    pub trigger: Option<Trigger>,
+    /// What the crossed thresholds say, worst level first, shown instead of the message
+    pub alert: Option<(String, Level)>,
+    /// Ranges of the plot last drawn, for exports
+    pub scales: Option<Scales>,
+    /// Side panel with figures of each series
+    pub stats: bool,
+    /// Shown on the status line, the outcome of the last command
+    pub message: Option<String>,
+    /// Series shown by the single-series views
+    pub selected: usize,
+    pub bins: Bins,
+    /// Value axis of the series view
+    pub yscale: Axis,
+    /// How each view with a value axis picks its range
+    pub yranges: HashMap<View, YRange>,
+    pub orientation: Orientation,
+    pub palette: Palette,
+    /// Built-in and user themes, and the one in use
+    pub themes: Vec<Theme>,
+    pub theme: usize,
+    /// Samples per second for the spectrum, found from the timestamps when missing
+    pub rate: Option<f64>,
+    pub window: WindowFunction,
+    pub log_freq: bool,
+    /// Samples per window of the spectrogram
+    pub fft_size: usize,
+}
+
+impl AppState {
+
+    pub fn new(args: Args) -> io::Result<AppState> {
+        let Args { mut source, follow, from, to, scatter, density, derived, computed, bins, yscale, yranges, thresholds, trigger, palette, theme, rate, window, log_freq, fft_size, .. } = args;
+        let themes = theme::load().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let theme = match theme {
+            Some(name) => themes.iter().position(|t| t.name == name)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("unknown theme '{}'", name)))?,
+            None => 0,
+        };
+        let (bundle, offset) = source.load(follow)?;
+        let feed = if follow { Some(source.follow(offset)?) } else { None };
+        let default = yranges.iter().rev()
+            .find(|(view, _)| view.is_none())
+            .map_or(RangePolicy::Auto(yrange::PADDING), |(_, policy)| *policy);
+        let mut ranges = HashMap::new();
+        for view in &[View::Series, View::Bars, View::Heatmap, View::Spectrum] {
+            let policy = yranges.iter().rev()
+                .find(|(v, _)| *v == Some(*view))
+                .map_or(default, |(_, policy)| *policy);
+            ranges.insert(*view, YRange::new(policy));
+        }
+        let mut app = AppState {
+            state: State::Normal,
+            source,
+            bundle,
+            feed,
+            from,
+            to,
+            view: View::Series,
+            scatter,
+            density,
+            derived,
+            computed,
+            thresholds,
+            crossed: HashSet::new(),
+            trigger,
+            alert: None,
+            scales: None,
+            stats: false,
+            message: None,
+            selected: 0,
+            bins,
+            yscale,
+            yranges: ranges,
+            orientation: Orientation::Vertical,
+            palette,
+            themes,
+            theme,
+            rate,
+            window,
+            log_freq,
+            fft_size,
+        };
+        app.update_series();
+        Ok(app)
+    }
+
+    /// Switches to the theme called `name`, or to the next one when None
+    pub fn switch_theme(&mut self, name: Option<&str>) -> Result<(), String> {
+        self.theme = match name {
+            Some(name) => self.themes.iter().position(|t| t.name == name)
+                .ok_or_else(|| format!("unknown theme '{}', there are {}", name,
+                    self.themes.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", ")))?,
+            None => (self.theme + 1) % self.themes.len(),
+        };
+        theme::set(self.themes[self.theme].clone());
+        Ok(())
+    }
+
+    /// Recomputes the derived and formula series from the input columns
+    pub fn update_series(&mut self) {
+        derive::update(&mut self.bundle, &self.derived);
+        if let Err(e) = expr::update(&mut self.bundle, &self.computed) {
+            self.message = Some(e);
+        }
+    }
+
+    /// Adds a formula series, or redefines the one with the same name
+    pub fn compute(&mut self, computed: Computed) -> Result<(), String> {
+        let stream = computed.stream(&self.bundle)?;
+        self.bundle.set(&computed.name, stream);
+        match self.computed.iter().position(|c| c.name == computed.name) {
+            Some(i) => self.computed[i] = computed,
+            None => self.computed.push(computed),
+        }
+        Ok(())
+    }
+
+    /// Moves whatever the followed file got since the last call into the bundle
+    pub fn poll_feed(&mut self) {
+        if let Some(ref feed) = self.feed {
+            let mut rows = 0;
+            for row in feed.try_iter() {
+                self.source.append(&mut self.bundle, row);
+                rows += 1;
+            }
+            if rows > 0 { self.update_series() }
+        }
+    }
+
+    /// The part of a stream that is on screen: all of it, or the `from`..`to` slice of timed ones
+    fn frame<'a>(&self, s: &'a Stream<f32>) -> Option<Frame<'a, f32>> {
+        if s.is_empty() { return None }
+        if s.is_timed() && (self.from.is_some() || self.to.is_some()) {
+            let from = self.from.unwrap_or(f64::NEG_INFINITY);
+            let to = self.to.unwrap_or(f64::INFINITY);
+            s.frame_by_time(from, to)
+        }
+        else {
+            Some(s.frame(0, s.len()))
+        }
+    }
+
+    /// Frames of the series with something to show, along with their names
+    pub fn named_frames(&self) -> Vec<(&str, Frame<'_, f32>)> {
+        self.bundle.iter()
+            .filter_map(|(name, s)| Some((name.as_str(), self.frame(s)?)))
+            .collect()
+    }
+
+    /// Frames of the scatter pairs found in the bundle, along with their legend labels
+    pub fn scatter_frames(&self) -> (Vec<String>, Vec<FramePair<'_, f32>>) {
+        let mut names = Vec::new();
+        let mut pairs = Vec::new();
+        for (x, y) in &self.scatter {
+            let frames = self.bundle.get(x).and_then(|s| self.frame(s))
+                .zip(self.bundle.get(y).and_then(|s| self.frame(s)));
+            if let Some(pair) = frames {
+                names.push(format!("{} vs {}", y, x));
+                pairs.push(pair);
+            }
+        }
+        (names, pairs)
+    }
+
+    /// Name and frame of the selected series
+    pub fn selected_frame(&self) -> Option<(&str, Frame<'_, f32>)> {
+        let name = self.bundle.names().get(self.selected)?;
+        let frame = self.frame(&self.bundle.streams()[self.selected])?;
+        Some((name, frame))
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = if self.bundle.is_empty() { 0 } else { (self.selected + 1) % self.bundle.len() };
+    }
+
+    /// Tells on the status line where a click on the series or scatter plot landed
+    pub fn click(&mut self, yx: YX) {
+        if self.view != View::Series && self.view != View::Scatter { return }
+        if let Some((x, y)) = self.scales.and_then(|s| s.readout(yx)) {
+            self.message = Some(format!("x {}  y {}", x, y));
+        }
+    }
+
+    /// Compares the last sample of every series with the thresholds watching it. Tells whether
+    /// one got crossed since the last call, and keeps `alert` up to date. Runs the alert
+    /// command, if any, for the thresholds it fires on.
+    pub fn check_thresholds(&mut self) -> bool {
+        let mut crossed = HashSet::new();
+        let mut alerts = Vec::new();
+        for (s, (name, stream)) in self.bundle.iter().enumerate() {
+            let last = match stream.data().last() { Some(v) => *v, None => continue };
+            for (t, threshold) in self.thresholds.iter().enumerate() {
+                if threshold.applies_to(name) && threshold.crossed(last as f64) {
+                    crossed.insert((t, s));
+                    alerts.push((format!("{} {} at {}", name, threshold.label(), last), threshold.level));
+                }
+            }
+        }
+        if let Some(ref mut trigger) = self.trigger {
+            for outcome in trigger.check(&self.bundle, &self.thresholds) {
+                self.message = Some(outcome.unwrap_or_else(|e| e));
+            }
+        }
+        let new = crossed.difference(&self.crossed).next().is_some();
+        self.crossed = crossed;
+        alerts.sort_by_key(|(_, level)| *level != Level::Critical);
+        self.alert = alerts.first().map(|(_, level)| {
+            let texts: Vec<&str> = alerts.iter().map(|(text, _)| text.as_str()).collect();
+            (texts.join(", "), *level)
+        });
+        new
+    }
+
+    /// Writes the view to `path`, at `size` pixels for graphics. Tells how it went.
+    pub fn export(&self, path: &str, size: Option<(u32, u32)>) -> String {
+        export::export(self, path, size.unwrap_or((960, 540))).unwrap_or_else(|e| e)
+    }
+
+    /// Range of the plot in `view`. Views without a value axis of their own share the one of
+    /// the series view.
+    pub fn yrange(&self, view: View) -> &YRange {
+        self.yranges.get(&view).unwrap_or_else(|| &self.yranges[&View::Series])
+    }
+
+    /// Moves the range of the current view on to its next policy
+    pub fn cycle_yrange(&mut self) {
+        let view = if self.yranges.contains_key(&self.view) { self.view } else { View::Series };
+        if let Some(range) = self.yranges.get_mut(&view) {
+            range.cycle();
+            self.message = Some(format!("y range: {}", range.policy.name()));
+        }
+    }
+
+    /// Switches to `view`, or back to the plain series when already there
+    pub fn toggle_view(&mut self, view: View) {
+        self.view = if self.view == view { View::Series } else { view };
+    }
+
 }
 
 #[derive(Debug)]
@@ -62,20 +462,20 @@ pub enum Fun {
 
 impl Fun {
     pub fn from(ch: i32) -> Option<Fun> {
-        match ch {
-            KEY_F1 => Some(Fun::F1),
-            KEY_F2  => Some(Fun::F2),
-            KEY_F3  => Some(Fun::F3),
-            KEY_F4  => Some(Fun::F4),
-            KEY_F5  => Some(Fun::F5),
-            KEY_F6  => Some(Fun::F6),
-            KEY_F7  => Some(Fun::F7),
-            KEY_F8  => Some(Fun::F8),
-            KEY_F9  => Some(Fun::F9),
-            KEY_F10 => Some(Fun::F10),
-            KEY_F11 => Some(Fun::F11),
-            KEY_F12 => Some(Fun::F12),
-                _ => None
+        match ch - KEY_F0 {
+            1 => Some(Fun::F1),
+            2 => Some(Fun::F2),
+            3 => Some(Fun::F3),
+            4 => Some(Fun::F4),
+            5 => Some(Fun::F5),
+            6 => Some(Fun::F6),
+            7 => Some(Fun::F7),
+            8 => Some(Fun::F8),
+            9 => Some(Fun::F9),
+            10 => Some(Fun::F10),
+            11 => Some(Fun::F11),
+            12 => Some(Fun::F12),
+            _ => None
         }
     }
 }
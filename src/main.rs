@@ -2,6 +2,20 @@ mod draw;
 mod interact;
 mod core;
 mod stream;
+mod time;
+mod axis;
+mod fft;
+mod source;
+mod args;
+mod derive;
+mod expr;
+mod yrange;
+mod threshold;
+mod alert;
+mod export;
+
+use std::env;
+use std::process;
 
 // use stream::Numeric;
 
@@ -22,11 +36,40 @@ fn test_raster() {
 
 fn main() {
 
-    draw::start_ncurses_mode();
+    let args = match args::Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            if !msg.is_empty() { eprintln!("grust: {}", msg) }
+            eprint!("{}", args::USAGE);
+            process::exit(2)
+        }
+    };
+    let sparkline = args.sparkline;
+    let mut app = match interact::AppState::new(args) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("grust: {}", e);
+            process::exit(1)
+        }
+    };
+
+    if let Some(width) = sparkline {
+        if let Some(ref msg) = app.message { eprintln!("grust: {}", msg) }
+        for (name, frame) in app.named_frames() {
+            let last = frame.data().last().copied().unwrap_or(f32::NAN);
+            println!("{} {} {}", name, draw::sparkline::sparkline(&frame, width), last);
+        }
+        return
+    }
+
+    if let Err(e) = draw::start_terminal() {
+        eprintln!("grust: {}", e);
+        process::exit(1)
+    }
     {
-        while core::start_interface().is_ok() { }
+        while core::start_interface(&mut app).is_ok() { }
     };
-    draw::end_ncurses_mode();
+    draw::end_terminal();
 
     // test_raster()
 }
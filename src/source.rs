@@ -0,0 +1,160 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use crate::stream::{Stream, StreamBundle};
+use crate::time::{self, TimeFormat, Timestamp};
+
+/// Where the x coordinate of each sample comes from
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum TimeSource {
+    /// No timestamps, samples are evenly spaced by their index
+    Index,
+    /// Parsed out of the given column (1-based, as in `cut -f`)
+    Column(usize),
+    /// Wall-clock time at which the line was read
+    Arrival,
+}
+
+/// One parsed line of the input
+#[derive(Debug)]
+pub struct Row {
+    pub time: Option<Timestamp>,
+    pub values: Vec<f32>,
+}
+
+/// A column file: one sample per line, fields split by commas or whitespace. An optional header
+/// line names the columns, otherwise they are called `c1`, `c2`... after their position.
+#[derive(Clone,Debug)]
+pub struct Source {
+    pub path: String,
+    pub time: TimeSource,
+    pub format: TimeFormat,
+    names: Vec<String>,
+}
+
+fn split_fields(line: &str) -> Vec<&str> {
+    if line.contains(',') {
+        line.split(',').map(|f| f.trim()).collect()
+    }
+    else {
+        line.split_whitespace().collect()
+    }
+}
+
+impl Source {
+
+    pub fn new(path: &str, time: TimeSource, format: TimeFormat) -> Source {
+        Source {
+            path: path.to_string(),
+            time,
+            format,
+            names: Vec::new(),
+        }
+    }
+
+    fn is_time_column(&self, i: usize) -> bool {
+        self.time == TimeSource::Column(i + 1)
+    }
+
+    pub fn parse_row(&self, line: &str) -> Option<Row> {
+        let fields = split_fields(line);
+        let time = match self.time {
+            TimeSource::Index => None,
+            TimeSource::Column(c) => Some(time::parse(fields.get(c.checked_sub(1)?)?, self.format)?),
+            TimeSource::Arrival => Some(time::now()),
+        };
+        let values = fields.iter().enumerate()
+            .filter(|(i, _)| !self.is_time_column(*i))
+            .map(|(_, f)| f.parse::<f32>().ok())
+            .collect::<Option<Vec<f32>>>()?;
+        if values.is_empty() { return None }
+        Some(Row { time, values })
+    }
+
+    fn parse_header(&mut self, line: &str) -> bool {
+        if self.parse_row(line).is_some() { return false }
+        self.names = split_fields(line).iter().enumerate()
+            .filter(|(i, _)| !self.is_time_column(*i))
+            .map(|(_, f)| f.to_string())
+            .collect();
+        true
+    }
+
+    fn default_names(&mut self, columns: usize) {
+        let mut names = Vec::new();
+        let mut c = 0;
+        while names.len() < columns {
+            c += 1;
+            if self.time != TimeSource::Column(c) { names.push(format!("c{}", c)) }
+        }
+        self.names = names;
+    }
+
+    /// Adds a row to the bundle, creating its streams on the first one
+    pub fn append(&mut self, bundle: &mut StreamBundle<f32>, row: Row) {
+        if bundle.is_empty() {
+            if self.names.len() < row.values.len() { self.default_names(row.values.len()) }
+            for (name, value) in self.names.iter().zip(row.values.iter()) {
+                let stream = match row.time {
+                    Some(t) => Stream::with_time(std::iter::once(*value), vec![t]),
+                    None => Stream::new(std::iter::once(*value)),
+                };
+                bundle.push(name, stream);
+            }
+        }
+        else {
+            for (stream, value) in bundle.streams_mut().iter_mut().zip(row.values.iter()) {
+                stream.push(*value, row.time);
+            }
+        }
+    }
+
+    /// Reads the whole file. When `follow` is set the trailing partial line is left out, and the
+    /// returned offset tells where to resume reading.
+    pub fn load(&mut self, follow: bool) -> io::Result<(StreamBundle<f32>, u64)> {
+        let text = fs::read_to_string(&self.path)?;
+        let end = if follow { text.rfind('\n').map_or(0, |i| i + 1) } else { text.len() };
+
+        let mut bundle = StreamBundle::new();
+        let mut lines = text[..end].lines().filter(|l| !l.trim().is_empty()).peekable();
+        if let Some(first) = lines.peek() {
+            if self.parse_header(first) { lines.next(); }
+        }
+        for line in lines {
+            if let Some(row) = self.parse_row(line) { self.append(&mut bundle, row) }
+        }
+        Ok((bundle, end as u64))
+    }
+
+    /// Keeps reading lines appended to the file from `offset` on, like `tail -f`
+    pub fn follow(&self, offset: u64) -> io::Result<Receiver<Row>> {
+        let mut file = fs::File::open(&self.path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let source = self.clone();
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut reader = BufReader::new(file);
+            let mut line = String::new();
+            loop {
+                match reader.read_line(&mut line) {
+                    Ok(0) => thread::sleep(Duration::from_millis(100)),
+                    Ok(_) if line.ends_with('\n') => {
+                        if let Some(row) = source.parse_row(&line) {
+                            if tx.send(row).is_err() { return }
+                        }
+                        line.clear();
+                    },
+                    Ok(_) => {},
+                    Err(_) => return,
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+}
@@ -2,14 +2,23 @@
 
 use std::fs;
 use std::iter::Iterator;
+
+use crate::axis::Axis;
+use crate::time::Timestamp;
+
 type Float = f32;
 
+/// NaN, for a sample without a value such as a formula dividing by zero
+fn is_nan<T: PartialOrd>(x: &T) -> bool {
+    x.partial_cmp(x).is_none()
+}
+
 fn smin<'a, T: PartialOrd>(x1: &'a T, x2: &'a T) -> &'a T {
-    if *x1 < *x2 { x1 } else { x2 }
+    if *x1 < *x2 || is_nan(x2) { x1 } else { x2 }
 }
 
 fn smax<'a, T: PartialOrd>(x1: &'a T, x2: &'a T) -> &'a T {
-    if *x1 > *x2 { x1 } else { x2 }
+    if *x1 > *x2 || is_nan(x2) { x1 } else { x2 }
 }
 
 fn min<'a, T: PartialOrd>(v: &'a [T]) -> &'a T {
@@ -27,6 +36,7 @@ pub trait Numeric: PartialOrd+Copy+Into<f32>+Into<f64> {}
 #[derive(Debug)]
 pub struct Stream<T: Numeric> {
     data: Vec<T>,
+    time: Option<Vec<Timestamp>>,
     max: T,
     min: T,
 }
@@ -44,30 +54,106 @@ impl<T: Numeric> Stream<T> {
 
         Self {
             data: data,
+            time: None,
             max: max,
             min: min,
         }
 
     }
 
+    /// Stream where every sample carries its own timestamp. Samples are put in time order, as
+    /// frames are looked up by time with a binary search.
+    pub fn with_time<C, D>(collection: C, time: Vec<Timestamp>) -> Self
+        where C: Iterator<Item=D>,
+              D: Into<T>
+    {
+        let mut stream = Self::new(collection);
+        assert_eq!(stream.data.len(), time.len(), "one timestamp per sample");
+        if time.windows(2).all(|t| t[0] <= t[1]) {
+            stream.time = Some(time);
+            return stream
+        }
+        let mut samples: Vec<(Timestamp, T)> = time.into_iter().zip(stream.data).collect();
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (time, data) = samples.into_iter().unzip();
+        stream.data = data;
+        stream.time = Some(time);
+        stream
+    }
+
     pub fn grow<C, D>(&mut self, new_data: C)
         where C: Iterator<Item=D>, D: Into<T>
         {
             let data: Vec<T> = new_data.into_iter().map(|d| d.into()).collect();
+            if data.is_empty() { return }
             let new_max = max(&data);
             let new_min = min(&data);
 
-            if *new_max > self.max { self.max = *new_max }
-            if *new_min < self.min { self.min = *new_min }
+            if *new_max > self.max || is_nan(&self.max) { self.max = *new_max }
+            if *new_min < self.min || is_nan(&self.min) { self.min = *new_min }
             self.data.extend(data)
         }
 
-    pub fn frame(&self, ini: usize, end: usize) -> Frame<T> {
+    pub fn grow_timed<C, D>(&mut self, new_data: C, time: Vec<Timestamp>)
+        where C: Iterator<Item=D>, D: Into<T>
+        {
+            let len = self.data.len();
+            self.grow(new_data);
+            assert_eq!(self.data.len() - len, time.len(), "one timestamp per sample");
+            if self.time.is_some() {
+                for t in time { self.push_time(Some(t)) }
+            }
+        }
+
+    /// Appends a single sample, `time` is ignored by streams without timestamps
+    pub fn push(&mut self, value: T, time: Option<Timestamp>) {
+        if value > self.max || is_nan(&self.max) { self.max = value }
+        if value < self.min || is_nan(&self.min) { self.min = value }
+        self.data.push(value);
+        self.push_time(time);
+    }
+
+    /// Keeps one timestamp per sample on timed streams: samples without one take their arrival
+    /// time, and none goes before the last so frames can still be found by time
+    fn push_time(&mut self, time: Option<Timestamp>) {
+        if let Some(t) = &mut self.time {
+            let time = time.unwrap_or_else(crate::time::now);
+            t.push(t.last().map_or(time, |last| last.max(time)));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn is_timed(&self) -> bool {
+        self.time.is_some()
+    }
+
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn time(&self) -> Option<&[Timestamp]> {
+        self.time.as_deref()
+    }
+
+    pub fn time_span(&self) -> Option<(Timestamp, Timestamp)> {
+        let time = self.time.as_ref()?;
+        Some((*time.first()?, *time.last()?))
+    }
+
+    pub fn frame(&self, ini: usize, end: usize) -> Frame<'_, T> {
         let stream_slice = &self.data[ini..end];
         let min = *min(stream_slice);
         let max = *max(stream_slice);
         Frame {
             stream: stream_slice,
+            time: self.time.as_ref().map(|t| &t[ini..end]),
             ini: ini,
             end: end,
             min: min,
@@ -75,25 +161,87 @@ impl<T: Numeric> Stream<T> {
         }
     }
 
+    /// Frame holding the samples with `from <= t <= to`. None for streams without timestamps
+    /// or when no sample falls inside the range.
+    pub fn frame_by_time(&self, from: Timestamp, to: Timestamp) -> Option<Frame<'_, T>> {
+        let time = self.time.as_ref()?;
+        let ini = time.partition_point(|t| *t < from);
+        let end = time.partition_point(|t| *t <= to);
+        if ini < end { Some(self.frame(ini, end)) } else { None }
+    }
+
 }
 
 pub struct StreamBundle<T: Numeric> {
     streams: Vec<Stream<T>>,
+    names: Vec<String>,
     vscale: Float,
     hscale: Float,
 }
 
-// impl StreamBundle {
-//     pub fn new(streams: Vec<Stream<T>>) -> StreamBundle {
-//         StreamBundle {
-//         }
-//     }
-// }
+impl<T: Numeric> StreamBundle<T> {
+
+    pub fn new() -> StreamBundle<T> {
+        StreamBundle {
+            streams: Vec::new(),
+            names: Vec::new(),
+            vscale: 1.,
+            hscale: 1.,
+        }
+    }
+
+    pub fn push(&mut self, name: &str, stream: Stream<T>) {
+        self.names.push(name.to_string());
+        self.streams.push(stream);
+    }
+
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn streams(&self) -> &[Stream<T>] {
+        &self.streams
+    }
+
+    pub fn streams_mut(&mut self) -> &mut [Stream<T>] {
+        &mut self.streams
+    }
+
+    /// Replaces the stream called `name`, or adds it when there is none
+    pub fn set(&mut self, name: &str, stream: Stream<T>) {
+        match self.names.iter().position(|n| n == name) {
+            Some(i) => self.streams[i] = stream,
+            None => self.push(name, stream),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Stream<T>> {
+        let i = self.names.iter().position(|n| n == name)?;
+        Some(&self.streams[i])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=(&String, &Stream<T>)> {
+        self.names.iter().zip(self.streams.iter())
+    }
+
+}
 
 
+/// An x frame and a y frame, plotted against each other
+pub type FramePair<'a, T> = (Frame<'a, T>, Frame<'a, T>);
+
 #[derive(Debug)]
 pub struct Frame<'a, T: Numeric> {
     stream: &'a [T],
+    time: Option<&'a [Timestamp]>,
     ini: usize,
     end: usize,
     min: T,
@@ -106,31 +254,252 @@ impl<T: Numeric> Frame<'_, T> {
         (Into::<f32>::into(self.max) - Into::<f32>::into(self.min)) / 10_f32
     }
 
+    pub fn data(&self) -> &[T] {
+        self.stream
+    }
+
+    pub fn time(&self) -> Option<&[Timestamp]> {
+        self.time
+    }
+
+    pub fn range(&self) -> (usize, usize) {
+        (self.ini, self.end)
+    }
+
+    pub fn min(&self) -> T {
+        self.min
+    }
+
+    pub fn max(&self) -> T {
+        self.max
+    }
+
+    pub fn time_span(&self) -> Option<(Timestamp, Timestamp)> {
+        let time = self.time?;
+        Some((*time.first()?, *time.last()?))
+    }
+
+    /// Bins the frame into cells of `vscale` units high and `hscale` wide. The width is
+    /// measured in samples, or in seconds for frames with timestamps, so irregularly sampled
+    /// data keeps its real spacing.
     pub fn raster(&self, vscale: f32, hscale: f32) -> Raster {
+        self.raster_at(vscale, hscale, Into::<f32>::into(self.min), self.time.map(|t| t[0]))
+    }
+
+    /// Same as `raster`, but with the bottom row at `vmin` and, for timed frames, the first
+    /// column at `t0` instead of the frame's own minimum and first timestamp. Lets several
+    /// frames share the same axes. Samples below `vmin` or before `t0` are dropped.
+    pub fn raster_at(&self, vscale: f32, hscale: f32, vmin: f32, t0: Option<Timestamp>) -> Raster {
+        self.raster_on(Axis::Linear, vscale, hscale, vmin, t0)
+    }
+
+    /// Same as `raster_at` over a non linear value axis. Values go through `vaxis.forward`
+    /// first, `vscale` and `vmin` are in the forward coordinate, and values the axis can't
+    /// show (zero or negatives on a log axis) are dropped.
+    pub fn raster_on(&self, vaxis: Axis, vscale: f32, hscale: f32, vmin: f32, t0: Option<Timestamp>) -> Raster {
 
-        let baseline = Into::<f32>::into(self.min) / vscale;
+        let baseline = vmin / vscale;
+        let scaled = |v: T| vaxis.forward(Into::<f64>::into(v)) as f32 / vscale - baseline;
 
-        let r: Vec<(usize,usize)> = self.stream.iter().enumerate()
-            .map(|(i, j)| {
-                let x = (i as f32 / hscale).floor() as usize;
-                let y = (Into::<f32>::into(*j) / vscale - baseline).floor() as usize;
-                (x, y)
-            })
-        .collect();
+        let column = |i: usize| -> f32 {
+            match (self.time, t0) {
+                (Some(time), Some(t0)) => ((time[i] - t0) / hscale as f64) as f32,
+                _ => i as f32 / hscale,
+            }
+        };
 
-        let hmax = r[r.len()-1].0 +1;
-        let vmax = (Into::<f32>::into(self.max) / vscale - baseline).floor() as usize +1;
+        let (r, heights): (Vec<(usize,usize)>, Vec<f32>) = self.stream.iter().enumerate()
+            .map(|(i, j)| (column(i), scaled(*j)))
+            .filter(|(x, y)| *x >= 0. && *y >= 0. && y.is_finite())
+            .map(|(x, y)| ((x.floor() as usize, y.floor() as usize), y))
+            .unzip();
+
+        let hmax = r.last().map_or(0, |b| b.0 + 1);
+        let vmax = scaled(self.max).floor().max(0.) as usize +1;
         // let vmax = *max(&r.iter().map(|t| t.1).collect::<Vec<usize>>()[..]) +1;
         Raster {
             bins: r,
+            heights,
             hmax: hmax,
             vmax: vmax
         }
     }
+
+    /// Scatter of this frame (x) against `other` (y), sample by sample. Each axis has its own
+    /// scale and origin, so series of very different magnitudes can be compared. Pairs falling
+    /// left of `hmin` or under `vmin` are dropped.
+    pub fn raster_xy(&self, other: &Frame<T>, hscale: f32, vscale: f32, hmin: f32, vmin: f32) -> Raster {
+
+        let (r, heights): (Vec<(usize,usize)>, Vec<f32>) = self.stream.iter().zip(other.stream.iter())
+            .map(|(x, y)| (Into::<f32>::into(*x), Into::<f32>::into(*y)))
+            .map(|(x, y)| ((x - hmin) / hscale, (y - vmin) / vscale))
+            .filter(|(x, y)| *x >= 0. && *y >= 0.)
+            .map(|(x, y)| ((x.floor() as usize, y.floor() as usize), y))
+            .unzip();
+
+        let hmax = r.iter().map(|b| b.0 + 1).max().unwrap_or(0);
+        let vmax = r.iter().map(|b| b.1 + 1).max().unwrap_or(0);
+        Raster {
+            bins: r,
+            heights,
+            hmax,
+            vmax,
+        }
+    }
+}
+
+/// How many bins a histogram gets
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Bins {
+    Count(usize),
+    /// Bin width of 2 IQR / n^(1/3), robust to outliers
+    FreedmanDiaconis,
+    /// log2(n) + 1 bins, fine for roughly normal data
+    Sturges,
+}
+
+impl Bins {
+    pub fn from(s: &str) -> Option<Bins> {
+        match s {
+            "fd" => Some(Bins::FreedmanDiaconis),
+            "sturges" => Some(Bins::Sturges),
+            _ => s.parse::<usize>().ok().filter(|n| *n > 0).map(Bins::Count),
+        }
+    }
+}
+
+/// Value at quantile `q` (0 to 1) of sorted data, interpolating between neighbours
+pub fn quantile(sorted: &[f32], q: f32) -> f32 {
+    if sorted.is_empty() { return f32::NAN }
+    let pos = q.clamp(0., 1.) * (sorted.len() - 1) as f32;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f32)
+}
+
+/// Summary of the values of a frame
+#[derive(Copy,Clone,Debug)]
+pub struct Stats {
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub median: f32,
+    /// Population standard deviation
+    pub std_dev: f32,
+    pub p90: f32,
+    pub p95: f32,
+    pub p99: f32,
+    pub last: f32,
+}
+
+#[derive(Debug)]
+pub struct Histogram {
+    /// Lower edge of the first bin
+    pub min: f32,
+    pub width: f32,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+
+    /// (lower, upper) edges of bin `i`
+    pub fn edges(&self, i: usize) -> (f32, f32) {
+        let lower = self.min + i as f32 * self.width;
+        (lower, lower + self.width)
+    }
+
+    pub fn max_count(&self) -> usize {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+}
+
+impl<T: Numeric> Frame<'_, T> {
+
+    /// Frame values, NaN samples left out
+    fn values(&self) -> impl Iterator<Item=f64> + '_ {
+        self.stream.iter().map(|v| Into::<f64>::into(*v)).filter(|v| !v.is_nan())
+    }
+
+    /// Frame values in ascending order, NaN samples left out
+    pub fn sorted(&self) -> Vec<f32> {
+        let mut values: Vec<f32> = self.values().map(|v| v as f32).collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        values
+    }
+
+    pub fn mean(&self) -> f32 {
+        let (sum, n) = self.values().fold((0., 0), |(sum, n), v| (sum + v, n + 1));
+        (sum / n as f64) as f32
+    }
+
+    /// Population standard deviation
+    pub fn std_dev(&self) -> f32 {
+        let mean = self.mean() as f64;
+        let (squares, n) = self.values().fold((0., 0), |(sum, n), v| (sum + (v - mean).powi(2), n + 1));
+        (squares / n as f64).sqrt() as f32
+    }
+
+    /// Value below which a fraction `q` (0 to 1) of the frame falls. Sorts the frame, so
+    /// `stats` is cheaper when several are needed.
+    pub fn percentile(&self, q: f32) -> f32 {
+        quantile(&self.sorted(), q)
+    }
+
+    pub fn last(&self) -> Option<T> {
+        self.stream.last().copied()
+    }
+
+    /// All of the above, with the frame sorted once for every percentile
+    pub fn stats(&self) -> Stats {
+        let sorted = self.sorted();
+        Stats {
+            count: sorted.len(),
+            min: self.min.into(),
+            max: self.max.into(),
+            mean: self.mean(),
+            median: quantile(&sorted, 0.5),
+            std_dev: self.std_dev(),
+            p90: quantile(&sorted, 0.9),
+            p95: quantile(&sorted, 0.95),
+            p99: quantile(&sorted, 0.99),
+            last: self.last().map_or(f32::NAN, |v| v.into()),
+        }
+    }
+
+    /// Distribution of the frame values over evenly sized bins, at most `limit` of them
+    pub fn histogram(&self, bins: Bins, limit: usize) -> Histogram {
+        let (min, max) = (Into::<f32>::into(self.min), Into::<f32>::into(self.max));
+        let n = self.values().count();
+        let sturges = (n as f32).log2().ceil() as usize + 1;
+        let count = match bins {
+            Bins::Count(c) => c,
+            Bins::Sturges => sturges,
+            Bins::FreedmanDiaconis => {
+                let sorted = self.sorted();
+                let iqr = quantile(&sorted, 0.75) - quantile(&sorted, 0.25);
+                let width = 2. * iqr / (n as f32).cbrt();
+                if width > 0. { ((max - min) / width).ceil() as usize } else { sturges }
+            },
+        };
+        let count = count.clamp(1, limit.max(1));
+
+        let width = if max > min { (max - min) / count as f32 } else { 1. };
+        let mut counts = vec![0; count];
+        for v in self.values() {
+            let i = ((v as f32 - min) / width).floor() as usize;
+            // The maximum sits on the upper edge of the last bin
+            counts[i.min(count - 1)] += 1;
+        }
+        Histogram { min, width, counts }
+    }
+
 }
 
 pub struct Raster {
     bins: Vec<(usize, usize)>,
+    /// Exact height of each bin in rows, before flooring
+    heights: Vec<f32>,
     hmax: usize,
     vmax: usize
 }
@@ -151,8 +520,93 @@ impl Raster {
         }
         raster
     }
+
+    /// Like `raster`, but counting how many samples fell in each cell
+    pub fn counts(&self) -> Vec<Vec<u32>> {
+        let mut counts = vec![vec![0u32; self.vmax]; self.hmax];
+        for (i, j) in &self.bins {
+            counts[*i][*j] += 1;
+        }
+        counts
+    }
+
+    /// Alternative output as one bar per column, its height in eighths of a cell so it can be
+    /// drawn with block elements. Columns holding several samples get their mean, empty ones
+    /// stay at zero.
+    pub fn bars(&self) -> Vec<usize> {
+        let mut sums = vec![(0f32, 0usize); self.hmax];
+        for ((x, _), h) in self.bins.iter().zip(self.heights.iter()) {
+            sums[*x].0 += h;
+            sums[*x].1 += 1;
+        }
+        sums.iter()
+            .map(|(sum, n)| if *n > 0 { (sum / *n as f32 * 8.).round() as usize } else { 0 })
+            .collect()
+    }
+
+    /// Sample counts of every cell as a matrix, for heatmaps of how values spread over time
+    pub fn matrix(&self) -> Matrix {
+        let mut matrix = Matrix::new(self.hmax, self.vmax);
+        for (x, y) in &self.bins {
+            matrix.add(*x, *y, 1.);
+        }
+        matrix
+    }
+
+    pub fn bins(&self) -> &[(usize, usize)] {
+        &self.bins
+    }
+
+    /// Exact row of each bin, fractional
+    pub fn heights(&self) -> &[f32] {
+        &self.heights
+    }
+
+    /// (columns, rows) spanned by the bins
+    pub fn size(&self) -> (usize, usize) {
+        (self.hmax, self.vmax)
+    }
+}
+
+/// Values on a grid, indexed like a raster: (column, row) with row 0 at the bottom
+#[derive(Clone,Debug)]
+pub struct Matrix {
+    cols: usize,
+    rows: usize,
+    values: Vec<f32>,
 }
 
+impl Matrix {
+
+    pub fn new(cols: usize, rows: usize) -> Matrix {
+        Matrix { cols, rows, values: vec![0.; cols * rows] }
+    }
+
+    /// (columns, rows)
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols, self.rows)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        self.values[x * self.rows + y]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, v: f32) {
+        self.values[x * self.rows + y] = v;
+    }
+
+    pub fn add(&mut self, x: usize, y: usize, v: f32) {
+        self.values[x * self.rows + y] += v;
+    }
+
+    /// Smallest and largest finite value
+    pub fn range(&self) -> (f32, f32) {
+        self.values.iter()
+            .filter(|v| v.is_finite())
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| (min.min(*v), max.max(*v)))
+    }
+
+}
 
 pub fn read_number_file(filename: &str) -> Vec<f32> {
     let r_file = fs::read_to_string(filename);
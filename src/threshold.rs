@@ -0,0 +1,79 @@
+use crate::time::{self, TimeFormat};
+
+/// How bad crossing a threshold is, and so its color
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Level {
+    Warning,
+    Critical,
+}
+
+/// Where a reference line sits
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Limit {
+    /// Horizontal line, samples over it are out of bounds
+    Above(f64),
+    /// Horizontal line, samples under it are out of bounds
+    Below(f64),
+    /// Vertical line at a time, or at a sample index for untimed data
+    At(f64),
+}
+
+/// A reference line drawn across the plot. Horizontal ones may watch a single series.
+#[derive(Clone,Debug)]
+pub struct Threshold {
+    pub series: Option<String>,
+    pub limit: Limit,
+    pub level: Level,
+}
+
+impl Threshold {
+
+    /// `[SERIES]>VALUE`, `[SERIES]<VALUE` or `@TIME`, followed by `,warn` for a warning rather
+    /// than a critical level. TIME is a sample index, epoch seconds or an RFC 3339 instant.
+    pub fn parse(s: &str) -> Result<Threshold, String> {
+        let (spec, level) = match s.strip_suffix(",warn") {
+            Some(spec) => (spec, Level::Warning),
+            None => (s.strip_suffix(",crit").unwrap_or(s), Level::Critical),
+        };
+        let number = |v: &str| v.trim().parse::<f64>().map_err(|_| format!("bad threshold value '{}'", v));
+
+        if let Some(at) = spec.strip_prefix('@') {
+            let x = time::parse(at, TimeFormat::Auto).ok_or(format!("can't read time '{}'", at))?;
+            return Ok(Threshold { series: None, limit: Limit::At(x), level })
+        }
+        let i = spec.find(['>', '<']).ok_or(format!("expected SERIES>VALUE, got '{}'", s))?;
+        let value = number(&spec[i + 1..])?;
+        let limit = if spec[i..].starts_with('>') { Limit::Above(value) } else { Limit::Below(value) };
+        let series = Some(spec[..i].trim().to_string()).filter(|n| !n.is_empty());
+        Ok(Threshold { series, limit, level })
+    }
+
+    /// Whether the threshold watches the series called `name`
+    pub fn applies_to(&self, name: &str) -> bool {
+        match (&self.series, self.limit) {
+            (_, Limit::At(_)) => false,
+            (Some(series), _) => series == name,
+            (None, _) => true,
+        }
+    }
+
+    /// Whether `v` is out of bounds
+    pub fn crossed(&self, v: f64) -> bool {
+        match self.limit {
+            Limit::Above(limit) => v > limit,
+            Limit::Below(limit) => v < limit,
+            Limit::At(_) => false,
+        }
+    }
+
+    /// Short description, as in `latency>200`
+    pub fn label(&self) -> String {
+        let series = self.series.as_deref().unwrap_or("");
+        match self.limit {
+            Limit::Above(v) => format!("{}>{}", series, v),
+            Limit::Below(v) => format!("{}<{}", series, v),
+            Limit::At(x) => format!("@{}", x),
+        }
+    }
+
+}
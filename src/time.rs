@@ -0,0 +1,215 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since the unix epoch, UTC. Millisecond timestamps keep their fraction.
+pub type Timestamp = f64;
+
+const MINUTE: f64 = 60.;
+const HOUR: f64 = 60. * MINUTE;
+const DAY: f64 = 24. * HOUR;
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum TimeFormat {
+    EpochSeconds,
+    EpochMillis,
+    Rfc3339,
+    /// Guess per value: RFC3339 if it looks like a date, otherwise epoch seconds or
+    /// milliseconds depending on the magnitude
+    Auto,
+}
+
+impl TimeFormat {
+    pub fn from(s: &str) -> Option<TimeFormat> {
+        match s {
+            "s" | "epoch" => Some(TimeFormat::EpochSeconds),
+            "ms" => Some(TimeFormat::EpochMillis),
+            "rfc3339" | "iso" => Some(TimeFormat::Rfc3339),
+            "auto" => Some(TimeFormat::Auto),
+            _ => None
+        }
+    }
+}
+
+pub fn now() -> Timestamp {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs_f64(),
+        Err(_) => 0.,
+    }
+}
+
+pub fn parse(s: &str, format: TimeFormat) -> Option<Timestamp> {
+    let s = s.trim();
+    match format {
+        TimeFormat::EpochSeconds => s.parse::<f64>().ok(),
+        TimeFormat::EpochMillis => s.parse::<f64>().ok().map(|ms| ms / 1000.),
+        TimeFormat::Rfc3339 => parse_rfc3339(s),
+        TimeFormat::Auto => {
+            if s.contains('-') && s.len() >= 10 {
+                parse_rfc3339(s)
+            }
+            else {
+                // Anything past 1e11 seconds is in the year 5000, it has to be millis
+                let t = s.parse::<f64>().ok()?;
+                if t.abs() >= 1e11 { Some(t / 1000.) } else { Some(t) }
+            }
+        }
+    }
+}
+
+fn parse_fixed(s: &str, ini: usize, end: usize) -> Option<i64> {
+    let digits = s.get(ini..end)?;
+    if !digits.bytes().all(|b| b.is_ascii_digit()) { return None }
+    digits.parse::<i64>().ok()
+}
+
+/// Accepts `YYYY-MM-DDTHH:MM:SS[.frac](Z|±HH:MM)`. A space instead of the `T` and a missing
+/// offset (taken as UTC) are tolerated, as most log files write them that way.
+fn parse_rfc3339(s: &str) -> Option<Timestamp> {
+    let b = s.as_bytes();
+    if b.len() < 19 || b[4] != b'-' || b[7] != b'-' || b[13] != b':' || b[16] != b':' {
+        return None
+    }
+    if !matches!(b[10], b'T' | b't' | b' ') { return None }
+
+    let year = parse_fixed(s, 0, 4)?;
+    let month = parse_fixed(s, 5, 7)?;
+    let day = parse_fixed(s, 8, 10)?;
+    let hour = parse_fixed(s, 11, 13)?;
+    let minute = parse_fixed(s, 14, 16)?;
+    let second = parse_fixed(s, 17, 19)?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day)
+        || hour > 23 || minute > 59 || second > 60 {
+        return None
+    }
+
+    let mut rest = &s[19..];
+    let mut fraction = 0.;
+    if rest.starts_with('.') {
+        let len = rest[1..].bytes().take_while(|b| b.is_ascii_digit()).count();
+        if len == 0 { return None }
+        fraction = format!("0.{}", &rest[1..=len]).parse::<f64>().ok()?;
+        rest = &rest[len + 1..];
+    }
+
+    let offset = match rest {
+        "" | "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            if rest.len() != 6 || rest.as_bytes()[3] != b':' { return None }
+            sign * (parse_fixed(rest, 1, 3)? * 3600 + parse_fixed(rest, 4, 6)? * 60)
+        }
+    };
+
+    let days = days_from_civil(year, month, day);
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+    Some(seconds as f64 + fraction)
+}
+
+/// Days since 1970-01-01 for a proleptic gregorian date (Howard Hinnant's algorithm)
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Inverse of `days_from_civil`, returns (year, month, day)
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (if m <= 2 { yoe + era * 400 + 1 } else { yoe + era * 400 }, m, d)
+}
+
+/// Short wall-clock label for an axis tick. The precision follows the distance between ticks,
+/// so a day-wide axis reads `MM-DD` and one with ticks under a second apart reads `MM:SS.mmm`.
+pub fn format_label(t: Timestamp, step: f64) -> String {
+    let ms = (t * 1000.).round() as i64;
+    let secs = ms.div_euclid(1000);
+    let (_, m, d) = civil_from_days(secs.div_euclid(86400));
+    let sod = secs.rem_euclid(86400);
+    let (hh, mm, ss) = (sod / 3600, sod % 3600 / 60, sod % 60);
+    if step >= DAY {
+        format!("{:02}-{:02}", m, d)
+    }
+    else if step >= MINUTE {
+        format!("{:02}:{:02}", hh, mm)
+    }
+    else if step >= 1. {
+        format!("{:02}:{:02}:{:02}", hh, mm, ss)
+    }
+    else {
+        format!("{:02}:{:02}.{:03}", mm, ss, ms.rem_euclid(1000))
+    }
+}
+
+/// Distances between time ticks that read well on a clock
+const TIME_STEPS: [f64; 25] = [
+    0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5,
+    1., 2., 5., 10., 15., 30.,
+    MINUTE, 2. * MINUTE, 5. * MINUTE, 10. * MINUTE, 15. * MINUTE, 30. * MINUTE,
+    HOUR, 3. * HOUR, 6. * HOUR, 12. * HOUR,
+];
+
+/// Picks the smallest clock-friendly step giving at most `n` ticks in [t0, t1] and returns it
+/// along with the ticks. Spans longer than that fall back to whole days.
+pub fn ticks(t0: Timestamp, t1: Timestamp, n: usize) -> (f64, Vec<Timestamp>) {
+    let span = t1 - t0;
+    let n = n.max(1) as f64;
+    let step = TIME_STEPS.iter()
+        .find(|step| span / **step <= n)
+        .copied()
+        .unwrap_or_else(|| DAY * (span / DAY / n).ceil().max(1.));
+
+    let first = (t0 / step).ceil() * step;
+    let ticks = (0..)
+        .map(|i| first + i as f64 * step)
+        .take_while(|t| *t <= t1)
+        .collect();
+    (step, ticks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_in_every_format() {
+        assert_eq!(parse("1700000000", TimeFormat::EpochSeconds), Some(1_700_000_000.));
+        assert_eq!(parse("1700000000500", TimeFormat::EpochMillis), Some(1_700_000_000.5));
+        assert_eq!(parse("2023-11-14T22:13:20Z", TimeFormat::Rfc3339), Some(1_700_000_000.));
+        assert_eq!(parse("2023-11-14 22:13:20.25", TimeFormat::Rfc3339), Some(1_700_000_000.25));
+        assert_eq!(parse("2023-11-15T00:13:20+02:00", TimeFormat::Rfc3339), Some(1_700_000_000.));
+        assert_eq!(parse("1969-12-31T23:59:59Z", TimeFormat::Rfc3339), Some(-1.));
+
+        // Guessed from the shape and the magnitude
+        assert_eq!(parse(" 2023-11-14T22:13:20Z ", TimeFormat::Auto), Some(1_700_000_000.));
+        assert_eq!(parse("1700000000", TimeFormat::Auto), Some(1_700_000_000.));
+        assert_eq!(parse("1700000000000", TimeFormat::Auto), Some(1_700_000_000.));
+
+        for bad in &["2023-13-14T22:13:20Z", "2023-11-14T22:13:20.Z", "2023-11-14T22:13:20+0200",
+                     "2023-11-14X22:13:20Z", "2023-11-14T22:13", "yesterday"] {
+            assert_eq!(parse(bad, TimeFormat::Rfc3339), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn labels_follow_the_step() {
+        let t = 1_700_000_000.999_6;
+        assert_eq!(format_label(t, DAY), "11-14");
+        assert_eq!(format_label(t, 5. * MINUTE), "22:13");
+        assert_eq!(format_label(t, 1.), "22:13:21");
+        assert_eq!(format_label(t - 0.5, 0.1), "13:20.500");
+    }
+}
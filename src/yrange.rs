@@ -0,0 +1,185 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use crate::axis::{self, Axis};
+
+/// Fraction of the span added above and below by the auto policies unless told otherwise
+pub const PADDING: f64 = 0.05;
+
+/// Seconds a sticky range waits before shrinking unless told otherwise
+const HOLD: f64 = 10.;
+
+/// How a plot picks the bottom and top of its value axis
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum RangePolicy {
+    /// Always these values, whatever the data
+    Fixed(f64, f64),
+    /// The span of the data on screen, plus a fraction of it on each side
+    Auto(f64),
+    /// Like auto, but growing at once and only shrinking after the data has fit in a smaller
+    /// range for the given number of seconds, so the scale doesn't jump on live data
+    Sticky(f64, f64),
+}
+
+impl RangePolicy {
+
+    /// `fixed:MIN:MAX`, `auto[:PADDING]` or `sticky[:SECONDS]`
+    pub fn from(s: &str) -> Option<RangePolicy> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let num = |i: usize| parts.get(i).and_then(|p| p.parse::<f64>().ok()).filter(|v| v.is_finite());
+        match (parts[0], parts.len()) {
+            ("fixed", 3) => match (num(1), num(2)) {
+                (Some(min), Some(max)) if max > min => Some(RangePolicy::Fixed(min, max)),
+                _ => None,
+            },
+            ("auto", 1) => Some(RangePolicy::Auto(PADDING)),
+            ("auto", 2) => num(1).filter(|p| *p >= 0.).map(RangePolicy::Auto),
+            ("sticky", 1) => Some(RangePolicy::Sticky(PADDING, HOLD)),
+            ("sticky", 2) => num(1).filter(|s| *s >= 0.).map(|hold| RangePolicy::Sticky(PADDING, hold)),
+            _ => None
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            RangePolicy::Fixed(min, max) => {
+                let step = (max - min) / 100.;
+                format!("fixed {} to {}", axis::format_value(min, step), axis::format_value(max, step))
+            },
+            RangePolicy::Auto(_) => String::from("auto"),
+            RangePolicy::Sticky(_, hold) => format!("sticky, shrinking after {}s", hold),
+        }
+    }
+
+}
+
+/// The value range of one plot, remembering what it showed last so sticky ranges can hold
+/// on to it. Plots only get to see the application state, hence the cells.
+#[derive(Debug)]
+pub struct YRange {
+    pub policy: RangePolicy,
+    /// Last range handed out, in the linear coordinate of the axis it was for
+    shown: Cell<Option<(f64, f64, Axis)>>,
+    /// Since when the data has fit in a smaller range than the one shown
+    shrinkable: Cell<Option<Instant>>,
+}
+
+impl YRange {
+
+    pub fn new(policy: RangePolicy) -> YRange {
+        YRange { policy, shown: Cell::new(None), shrinkable: Cell::new(None) }
+    }
+
+    /// Bottom and top of the value axis for data spanning `lo` to `hi`. Both the data span
+    /// and the result are in the linear coordinate of `axis` (see `Axis::forward`).
+    pub fn fit(&self, lo: f64, hi: f64, axis: Axis) -> (f64, f64) {
+        let pad = |p: f64| {
+            let span = if hi > lo { hi - lo } else { 1. };
+            (lo - span * p, hi + span * p)
+        };
+        let range = match self.policy {
+            RangePolicy::Fixed(min, max) => {
+                let (min, max) = (axis.forward(min), axis.forward(max));
+                // Bounds the axis can't show, like zero on a log axis, fall back on the data
+                (if min.is_finite() { min } else { lo }, if max.is_finite() { max } else { hi })
+            },
+            RangePolicy::Auto(p) => pad(p),
+            RangePolicy::Sticky(p, hold) => {
+                let (want_lo, want_hi) = pad(p);
+                match self.shown.get() {
+                    Some((shown_lo, shown_hi, shown_axis)) if shown_axis == axis => {
+                        // Grow right away, to the padded range so it doesn't creep up
+                        let grown = (if lo < shown_lo { want_lo } else { shown_lo },
+                                     if hi > shown_hi { want_hi } else { shown_hi });
+                        let smaller = want_lo > grown.0 || want_hi < grown.1;
+                        if !smaller {
+                            self.shrinkable.set(None);
+                            grown
+                        }
+                        else {
+                            let since = self.shrinkable.get().unwrap_or_else(Instant::now);
+                            self.shrinkable.set(Some(since));
+                            if since.elapsed() >= Duration::from_secs_f64(hold) {
+                                self.shrinkable.set(None);
+                                (want_lo, want_hi)
+                            }
+                            else {
+                                grown
+                            }
+                        }
+                    },
+                    _ => (want_lo, want_hi),
+                }
+            },
+        };
+        let range = if range.1 > range.0 { range } else { (range.0 - 1., range.0 + 1.) };
+        self.shown.set(Some((range.0, range.1, axis)));
+        range
+    }
+
+    /// Next policy when cycling with a key. Fixing keeps the range on screen.
+    pub fn cycle(&mut self) {
+        self.policy = match self.policy {
+            RangePolicy::Auto(p) => RangePolicy::Sticky(p, HOLD),
+            RangePolicy::Sticky(p, _) => match self.shown.get() {
+                Some((lo, hi, axis)) => RangePolicy::Fixed(axis.inverse(lo), axis.inverse(hi)),
+                None => RangePolicy::Auto(p),
+            },
+            RangePolicy::Fixed(..) => RangePolicy::Auto(PADDING),
+        };
+        self.shrinkable.set(None);
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_from_specs() {
+        assert_eq!(RangePolicy::from("fixed:-1:2.5"), Some(RangePolicy::Fixed(-1., 2.5)));
+        assert_eq!(RangePolicy::from("auto"), Some(RangePolicy::Auto(PADDING)));
+        assert_eq!(RangePolicy::from("auto:0.1"), Some(RangePolicy::Auto(0.1)));
+        assert_eq!(RangePolicy::from("sticky:30"), Some(RangePolicy::Sticky(PADDING, 30.)));
+        for bad in &["fixed:2:1", "fixed:1:1", "fixed:1", "fixed:a:2", "auto:-1", "auto:inf", "auto:1:2",
+                     "sticky:-2", "sticky:x", "", "tight"] {
+            assert_eq!(RangePolicy::from(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn fixed_and_auto_ranges() {
+        let fixed = YRange::new(RangePolicy::Fixed(0., 100.));
+        assert_eq!(fixed.fit(20., 30., Axis::Linear), (0., 100.));
+        // Zero can't be shown on a log axis, the data's bottom is used instead
+        assert_eq!(fixed.fit(0.5, 1., Axis::Log), (0.5, 2.));
+        let auto = YRange::new(RangePolicy::Auto(0.1));
+        assert_eq!(auto.fit(0., 10., Axis::Linear), (-1., 11.));
+        assert_eq!(auto.fit(5., 5., Axis::Linear), (4.9, 5.1));
+    }
+
+    #[test]
+    fn sticky_ranges_grow_at_once_and_shrink_after_the_hold() {
+        let sticky = YRange::new(RangePolicy::Sticky(0., 1e6));
+        assert_eq!(sticky.fit(0., 10., Axis::Linear), (0., 10.));
+        // Smaller data keeps the range shown
+        assert_eq!(sticky.fit(2., 5., Axis::Linear), (0., 10.));
+        assert!(sticky.shrinkable.get().is_some());
+        // Growing on one side doesn't give up the other
+        assert_eq!(sticky.fit(-1., 5., Axis::Linear), (-1., 10.));
+        // Data filling the range again starts the wait over
+        assert_eq!(sticky.fit(-1., 12., Axis::Linear), (-1., 12.));
+        assert_eq!(sticky.shrinkable.get(), None);
+        // Once the hold is over
+        sticky.fit(2., 5., Axis::Linear);
+        sticky.shrinkable.set(Some(Instant::now() - Duration::from_secs(1_000_001)));
+        assert_eq!(sticky.fit(2., 5., Axis::Linear), (2., 5.));
+        // Ranges on another axis don't carry over
+        assert_eq!(sticky.fit(1., 2., Axis::Log), (1., 2.));
+
+        let no_hold = YRange::new(RangePolicy::Sticky(0., 0.));
+        assert_eq!(no_hold.fit(0., 10., Axis::Linear), (0., 10.));
+        assert_eq!(no_hold.fit(2., 5., Axis::Linear), (2., 5.));
+    }
+}